@@ -9,6 +9,10 @@ use serde_json::{json, Value};
 pub trait Tank: Send + Sync {
     fn run(&mut self, commands: &mut Vec<Command>);
     fn on_event(&mut self, commands: &mut Vec<Command>, event: &Value);
+    /// The chassis class served on `/info` before the match: `scout`, `standard` or `heavy`.
+    fn chassis(&self) -> &str {
+        "standard"
+    }
 }
 
 pub struct HttpServer {
@@ -36,6 +40,7 @@ struct Path {}
 impl Path {
     pub const ROOT: &str = "";
     pub const PING: &str = "ping";
+    pub const INFO: &str = "info";
     pub const REQUEST_COMMANDS: &str = "request_commands";
     pub const REQUEST_COMMANDS_BY_EVENT: &str = "request_commands_by_event";
 }
@@ -103,6 +108,13 @@ fn handle_connection(mut stream: TcpStream, tank: &mut dyn Tank) {
     let response = match (method, path) {
         (Method::GET, Path::ROOT) => Response::ROOT,
         (Method::GET, Path::PING) => Response::PING,
+        (Method::GET, Path::INFO) => {
+            content = json!({ "chassis": tank.chassis() }).to_string();
+            Response {
+                status_line: StatusLine::OK,
+                content: &content,
+            }
+        }
         (Method::GET, Path::REQUEST_COMMANDS) => {
             tank.run(commands);
             content = json!(commands).to_string();
@@ -188,6 +200,93 @@ impl Commands {
     pub const CLEAR_COMMANDS: Command = 0b1 << 14;
     pub const DISABLE_RADAR: Command = 0b1 << 15;
     pub const ENABLE_RADAR: Command = 0b1 << 16;
+    pub const SCAN_WALLS: Command = 0b1 << 17;
+    /// sends the 8 bit payload in bits 19..=26 to teammates, see `Commands::team_message`
+    pub const TEAM_MESSAGE: Command = 0b1 << 18;
 
     pub const SELF_DESTRUCT: Command = 0b1 << 31;
+
+    /// FIRE with power above 1 in bits 27..=28, see `Commands::fire`
+    pub const FIRE_POWER_SHIFT: u32 = 27;
+    pub const FIRE_POWER_MASK: Command = 0b11 << Commands::FIRE_POWER_SHIFT;
+    pub const MAX_FIRE_POWER: u32 = 4;
+
+    pub const TEAM_MESSAGE_SHIFT: u32 = 19;
+    pub const TEAM_MESSAGE_MASK: Command = 0xFF << Commands::TEAM_MESSAGE_SHIFT;
+
+    pub fn team_message(payload: u8) -> Command {
+        Commands::TEAM_MESSAGE | (payload as Command) << Commands::TEAM_MESSAGE_SHIFT
+    }
+
+    pub fn team_message_payload(command: Command) -> u8 {
+        ((command & Commands::TEAM_MESSAGE_MASK) >> Commands::TEAM_MESSAGE_SHIFT) as u8
+    }
+
+    /// Fires a bullet doing `power` times the damage for `power` times the gun heat.
+    /// `power` is clamped to `1..=MAX_FIRE_POWER`.
+    pub fn fire(power: u32) -> Command {
+        Commands::FIRE
+            | (power.clamp(1, Commands::MAX_FIRE_POWER) - 1) << Commands::FIRE_POWER_SHIFT
+    }
+
+    pub fn fire_power(command: Command) -> u32 {
+        ((command & Commands::FIRE_POWER_MASK) >> Commands::FIRE_POWER_SHIFT) + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn team_message_payload_round_trips() {
+        for payload in [0u8, 1, 0x5A, u8::MAX] {
+            let command = Commands::team_message(payload);
+            assert_ne!(command & Commands::TEAM_MESSAGE, 0);
+            assert_eq!(Commands::team_message_payload(command), payload);
+        }
+    }
+
+    #[test]
+    fn team_message_payload_leaves_other_commands_alone() {
+        let command = Commands::team_message(u8::MAX) | Commands::MOVE_FORWARD;
+        assert_eq!(
+            command & !Commands::TEAM_MESSAGE_MASK,
+            Commands::TEAM_MESSAGE | Commands::MOVE_FORWARD
+        );
+        assert_eq!(Commands::team_message_payload(Commands::MOVE_FORWARD), 0);
+        // the payload sits between the command bits and SELF_DESTRUCT
+        assert_eq!(
+            Commands::TEAM_MESSAGE_MASK & (Commands::TEAM_MESSAGE | Commands::SELF_DESTRUCT),
+            0
+        );
+    }
+
+    #[test]
+    fn fire_power_round_trips() {
+        for power in 1..=Commands::MAX_FIRE_POWER {
+            let command = Commands::fire(power);
+            assert_ne!(command & Commands::FIRE, 0);
+            assert_eq!(Commands::fire_power(command), power);
+        }
+    }
+
+    #[test]
+    fn fire_power_is_clamped() {
+        assert_eq!(Commands::fire_power(Commands::fire(0)), 1);
+        assert_eq!(
+            Commands::fire_power(Commands::fire(Commands::MAX_FIRE_POWER + 3)),
+            Commands::MAX_FIRE_POWER
+        );
+        // plain FIRE from older bots is a standard shot
+        assert_eq!(Commands::fire(1), Commands::FIRE);
+        assert_eq!(Commands::fire_power(Commands::FIRE), 1);
+    }
+
+    #[test]
+    fn fire_power_and_team_messages_share_a_command() {
+        let command = Commands::fire(3) | Commands::team_message(0xAB);
+        assert_eq!(Commands::fire_power(command), 3);
+        assert_eq!(Commands::team_message_payload(command), 0xAB);
+    }
 }
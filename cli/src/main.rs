@@ -4,6 +4,13 @@ use clap::{arg, Command};
 use colored::*;
 
 const HOST: &str = "https://code-tanks.derrickliu.dev";
+const WRECK_MODES: [&str; 4] = ["remain", "removed", "static", "debris"];
+const GAME_MODES: [&str; 4] = [
+    "deathmatch",
+    "king_of_the_hill",
+    "capture_the_flag",
+    "respawn_deathmatch",
+];
 
 fn cli() -> Command {
     Command::new("ctcli")
@@ -33,7 +40,87 @@ fn cli() -> Command {
             Command::new("run")
                 .about("Run simulation with tank ids")
                 .arg_required_else_help(true)
-                .arg(arg!(<TANK_ID> ... "The tank ids to run")),
+                .arg(arg!(<TANK_ID> ... "The tank ids to run"))
+                .arg(arg!(--map <MAP> "A built-in map"))
+                .arg(
+                    arg!(--mode <MODE> "The game mode")
+                        .value_parser(GAME_MODES)
+                        .default_value(GAME_MODES[0]),
+                )
+                .arg(
+                    arg!(--teams <TEAMS> "Split the tanks into this many teams")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(
+                    arg!(--seed <SEED> "Seed for everything random in the match")
+                        .value_parser(clap::value_parser!(u64)),
+                )
+                .arg(
+                    arg!(--"pickup-interval" <TICKS> "Spawn a pickup every this many ticks")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(arg!(--"safe-zone" "Shrink a safe zone that damages tanks outside it"))
+                .arg(arg!(--"wrap-around" "Tanks and bullets wrap around the arena edges"))
+                .arg(
+                    arg!(--wreck <WRECK> "What destroyed tanks leave behind")
+                        .value_parser(WRECK_MODES),
+                )
+                .arg(arg!(--"velocity-collision-damage" "Scale collision damage with impact speed"))
+                .arg(
+                    arg!(--"max-collision-damage" <DAMAGE> "Cap on speed scaled collision damage")
+                        .value_parser(clap::value_parser!(i32)),
+                )
+                .arg(
+                    arg!(--"ricochet-bounces" <BOUNCES> "Times bullets bounce off walls")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(
+                    arg!(--"ricochet-damage-factor" <FACTOR> "Bullet damage multiplier per bounce")
+                        .value_parser(clap::value_parser!(f32)),
+                ),
+        )
+        .subcommand(
+            Command::new("local")
+                .about("Run simulation locally with tank ids or .rhai script files")
+                .arg_required_else_help(true)
+                .arg(arg!(<TANK> ... "The tank ids or script files to run"))
+                .arg(arg!(--map <MAP> "A built-in map or a map .json file"))
+                .arg(
+                    arg!(--mode <MODE> "The game mode")
+                        .value_parser(GAME_MODES)
+                        .default_value(GAME_MODES[0]),
+                )
+                .arg(
+                    arg!(--teams <TEAMS> "Split the tanks into this many teams")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(
+                    arg!(--seed <SEED> "Seed for everything random in the match")
+                        .value_parser(clap::value_parser!(u64)),
+                )
+                .arg(
+                    arg!(--"pickup-interval" <TICKS> "Spawn a pickup every this many ticks")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(arg!(--"safe-zone" "Shrink a safe zone that damages tanks outside it"))
+                .arg(arg!(--"wrap-around" "Tanks and bullets wrap around the arena edges"))
+                .arg(
+                    arg!(--wreck <WRECK> "What destroyed tanks leave behind")
+                        .value_parser(WRECK_MODES),
+                )
+                .arg(arg!(--"velocity-collision-damage" "Scale collision damage with impact speed"))
+                .arg(
+                    arg!(--"max-collision-damage" <DAMAGE> "Cap on speed scaled collision damage")
+                        .value_parser(clap::value_parser!(i32)),
+                )
+                .arg(
+                    arg!(--"ricochet-bounces" <BOUNCES> "Times bullets bounce off walls")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(
+                    arg!(--"ricochet-damage-factor" <FACTOR> "Bullet damage multiplier per bounce")
+                        .value_parser(clap::value_parser!(f32)),
+                ),
         )
 }
 
@@ -97,14 +184,60 @@ fn get_raw(tank_id: &str) {
     }
 }
 
-fn run_sim(tank_ids: Vec<String>) {
+// rule flags as the `name=value,...` options the simulator takes. Remote matches pick their
+// map by name there, local ones hand it to ctdesktop on its own since it can be a file.
+fn rule_options(sub_matches: &clap::ArgMatches, include_map: bool) -> String {
+    let mut options = vec![];
+    if let Some(teams) = sub_matches.get_one::<u32>("teams") {
+        options.push(format!("teams={}", teams));
+    }
+    if let Some(seed) = sub_matches.get_one::<u64>("seed") {
+        options.push(format!("seed={}", seed));
+    }
+    if let Some(pickup_interval) = sub_matches.get_one::<u32>("pickup-interval") {
+        options.push(format!("pickup_interval={}", pickup_interval));
+    }
+    if sub_matches.get_flag("safe-zone") {
+        options.push("safe_zone=true".to_string());
+    }
+    if sub_matches.get_flag("wrap-around") {
+        options.push("wrap_around=true".to_string());
+    }
+    if let Some(wreck) = sub_matches.get_one::<String>("wreck") {
+        options.push(format!("wreck={}", wreck));
+    }
+    if sub_matches.get_flag("velocity-collision-damage") {
+        options.push("velocity_collision_damage=true".to_string());
+    }
+    if let Some(damage) = sub_matches.get_one::<i32>("max-collision-damage") {
+        options.push(format!("max_collision_damage={}", damage));
+    }
+    if let Some(bounces) = sub_matches.get_one::<u32>("ricochet-bounces") {
+        options.push(format!("ricochet_bounces={}", bounces));
+    }
+    if let Some(factor) = sub_matches.get_one::<f32>("ricochet-damage-factor") {
+        options.push(format!("ricochet_damage_factor={}", factor));
+    }
+    if let Some(map) = sub_matches.get_one::<String>("map").filter(|_| include_map) {
+        options.push(format!("map={}", map));
+    }
+    options.join(",")
+}
+
+fn run_sim(tank_ids: Vec<String>, mode: &str, options: &str) {
+    let path = if options.is_empty() {
+        mode.to_string()
+    } else {
+        format!("{}/{}", mode, options)
+    };
+
     let output_raw = std::process::Command::new("curl")
         .arg("-s")
         .arg("-d")
         .arg(&tank_ids.join(" "))
         .arg("-X")
         .arg("POST")
-        .arg(format!("{}/run", HOST))
+        .arg(format!("{}/run/{}", HOST, path))
         .output()
         .expect("failed to communicate with CodeTanks server");
 
@@ -119,6 +252,31 @@ fn run_sim(tank_ids: Vec<String>) {
     }
 }
 
+fn run_local(tanks: Vec<String>, mode: &str, options: &str, map: Option<&String>) {
+    for tank in tanks.iter().filter(|f| f.ends_with(".rhai")) {
+        if !std::path::Path::new(tank).exists() {
+            println!("Path '{}' does not exist.", tank.red());
+            return;
+        }
+    }
+
+    let status = std::process::Command::new("ctdesktop")
+        .args(&tanks)
+        .args(["--mode", mode])
+        .args(if options.is_empty() {
+            vec![]
+        } else {
+            vec!["--rules", options]
+        })
+        .args(map.map_or(vec![], |f| vec!["--map", f.as_str()]))
+        .status()
+        .expect("failed to run ctdesktop");
+
+    if !status.success() {
+        println!("{}", "simulation failed".red());
+    }
+}
+
 fn main() {
     let matches = cli().get_matches();
 
@@ -160,6 +318,20 @@ fn main() {
                     .expect("required")
                     .map(|f| f.to_string())
                     .collect(),
+                sub_matches.get_one::<String>("mode").expect("defaulted"),
+                &rule_options(sub_matches, true),
+            );
+        }
+        Some(("local", sub_matches)) => {
+            run_local(
+                sub_matches
+                    .get_many::<String>("TANK")
+                    .expect("required")
+                    .map(|f| f.to_string())
+                    .collect(),
+                sub_matches.get_one::<String>("mode").expect("defaulted"),
+                &rule_options(sub_matches, false),
+                sub_matches.get_one::<String>("map"),
             );
         }
         _ => unreachable!(), // If all subcommands are defined above, anything else is unreachabe!()
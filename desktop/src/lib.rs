@@ -8,20 +8,29 @@ use bevy_rapier2d::prelude::RapierDebugRenderPlugin;
 use ct_api::Command;
 use ct_api::Commands;
 use ctengine::{
+    c_chassis::Chassis,
     c_client::{parse_commands, ClientTrait},
+    c_script_client::ScriptClient,
     c_event::CTEvent,
     c_tank::{AllTankInfo, TankInfo},
     s_apply_commands::apply_commands,
     s_request_commands::request_commands,
     s_save_commands::save_commands,
+    s_save_replay::{record_replay_events, save_replay, ReplayRecorder},
+    s_bullet_physics::bullet_physics,
+    s_request_commands_by_event::request_commands_by_event,
     s_setup_walls::setup_walls,
-    *, s_apply_history_transforms::apply_history_transforms,
+    s_verify_sim::{verify_replay, verify_sim},
+    rules::Rules,
+    map::Map,
+    *,
+    replay::REPLAY_EXTENSION,
 };
 use s_setup_desktop_tanks::setup_desktop_tanks;
 
 pub mod s_setup_desktop_tanks;
 use ctgraphics::{
-    s_setup_graphics::setup_graphics, s_setup_ground::setup_ground, CoreCTGraphicsPlugin, s_setup_reader_tanks::setup_reader_tanks,
+    s_setup_graphics::setup_graphics, s_setup_ground::setup_ground, CoreCTGraphicsPlugin, ReplayPlaybackPlugin,
 };
 use ctengine::core_plugin::CoreCTPlugin;
 
@@ -32,7 +41,7 @@ pub struct UseDummy {
     pub use_dummy: bool,
 }
 
-pub fn run_game(tank_hashes: &[String]) {
+pub fn run_game(tank_hashes: &[String], rules: Rules, map: Map) {
     let game_url: String = tank_hashes.join("-");
     let tank_infos = &tank_hashes
         .iter()
@@ -50,6 +59,8 @@ pub fn run_game(tank_hashes: &[String]) {
             return_from_run: true,
             ..default()
         })
+        .insert_resource(rules)
+        .insert_resource(map)
         .add_plugins(CoreCTPlugin)
         .add_plugins(CoreCTGraphicsPlugin)
         // .add_plugins(RapierDebugRenderPlugin::default())
@@ -70,6 +81,9 @@ pub fn run_game(tank_hashes: &[String]) {
         .run();
 
     for tank_info in tank_infos {
+        if ScriptClient::is_script(&tank_info.hash) {
+            continue;
+        }
         remove_tank(&tank_info.container_name);
         println!("removed {}", &tank_info.container_name);
     }
@@ -77,7 +91,7 @@ pub fn run_game(tank_hashes: &[String]) {
     println!("finished");
 }
 
-pub fn run_game_and_save(tank_hashes: &[String], ticks: u32) {
+pub fn run_game_and_save(tank_hashes: &[String], ticks: u32, rules: Rules, map: Map) {
     let game_url: String = tank_hashes.join("-");
     let tank_infos = &tank_hashes
         .iter()
@@ -90,6 +104,7 @@ pub fn run_game_and_save(tank_hashes: &[String], ticks: u32) {
         })
         .collect::<Vec<TankInfo>>();
 
+
     let mut f = File::create("./sim.txt").expect("Unable to create file");
     f.write_all(format!("{}\n", tank_hashes.join(",")).as_bytes())
         .expect("Unable to write data");
@@ -101,6 +116,8 @@ pub fn run_game_and_save(tank_hashes: &[String], ticks: u32) {
         })
         // .insert_resource(FixedTime::new_from_secs(TickState::DT))
         .insert_resource(MaxSimulationTicks(ticks))
+        .insert_resource(rules.clone())
+        .insert_resource(map.clone())
         .add_plugins(CoreCTPlugin)
         .add_plugins(CoreCTGraphicsPlugin)
         // .add_plugins(RapierDebugRenderPlugin::default())
@@ -112,9 +129,18 @@ pub fn run_game_and_save(tank_hashes: &[String], ticks: u32) {
             )
                 .chain(),
         )
+        .insert_resource(ReplayRecorder::create("./sim.ctr", tank_infos, ticks, &rules, &map))
         .add_systems(
             Update,
-            save_commands.after(request_commands).before(apply_commands),
+            (
+                (save_commands, save_replay)
+                    .chain()
+                    .after(request_commands)
+                    .before(apply_commands),
+                record_replay_events
+                    .after(bullet_physics)
+                    .before(request_commands_by_event),
+            ),
         )
         // .insert_resource(UseDummy {
         //     use_dummy: tank_hashes.is_empty(),
@@ -125,6 +151,9 @@ pub fn run_game_and_save(tank_hashes: &[String], ticks: u32) {
         .run();
 
     for tank_info in tank_infos {
+        if ScriptClient::is_script(&tank_info.hash) {
+            continue;
+        }
         remove_tank(&tank_info.container_name);
         println!("removed {}", &tank_info.container_name);
     }
@@ -135,14 +164,23 @@ pub fn run_game_and_save(tank_hashes: &[String], ticks: u32) {
 #[derive(Resource)]
 pub struct SimFilePath(pub String);
 
-pub fn load_tanks_from_file(mut state: ResMut<CustomAssetState>, asset_server: Res<AssetServer>, sim_file_path: Res<SimFilePath>) {
+pub fn load_tanks_from_file(
+    mut state: ResMut<CustomAssetState>,
+    mut replay_state: ResMut<ReplayAssetState>,
+    asset_server: Res<AssetServer>,
+    sim_file_path: Res<SimFilePath>,
+) {
     // state.handle = asset_server.load("./sim.txt");
     let file = &sim_file_path.0;
 
     println!("sim file: {}", file);
     info!("sim file: {}", file);
 
-    state.handle = asset_server.load(file);
+    if file.ends_with(REPLAY_EXTENSION) {
+        replay_state.handle = asset_server.load(file);
+    } else {
+        state.handle = asset_server.load(file);
+    }
 
     info!("got file");
 }
@@ -158,21 +196,11 @@ pub fn read_game(file: &str) {
             ..default()
         })
         // .insert_resource(FixedTime::new_from_secs(TickState::DT))
-        .add_plugins(CoreCTPlugin)
         .add_plugins(CoreCTGraphicsPlugin)
+        .add_plugins(ReplayPlaybackPlugin)
         // .add_plugins(RapierDebugRenderPlugin::default())
-        .init_resource::<CustomAssetState>()
         .insert_resource(SimFilePath(file.to_string()))
-        .add_asset::<CustomAsset>()
-        .init_asset_loader::<CustomAssetLoader>()
-        .add_systems(Startup, (load_tanks_from_file, setup_walls, setup_ground))
-        .add_systems(
-            Update,
-            (setup_reader_tanks.before(apply_commands), apply_history_transforms.after(request_commands).before(apply_commands))
-            // "request_commands",
-            // "apply_history_transforms",
-            // SystemStage::single_threaded().with_system(apply_history_transforms),
-        )
+        .add_systems(Startup, (load_tanks_from_file, setup_ground))
         .run();
 
     // for tank_info in tank_infos {
@@ -183,6 +211,24 @@ pub fn read_game(file: &str) {
     println!("finished");
 }
 
+// re-simulates the commands of a sim.txt or replay and checks the transforms match tick for tick,
+// replays are played with the rules and map they were recorded with
+pub fn verify_game(file: &str) {
+    let report = if file.ends_with(REPLAY_EXTENSION) {
+        let replay = std::fs::read(file).expect("Unable to read file");
+        verify_replay(&replay).expect("Unable to read replay")
+    } else {
+        let sim = std::fs::read_to_string(file).expect("Unable to read file");
+        verify_sim(&sim)
+    };
+
+    println!("verify {}: {}", file, report);
+
+    if !report.is_deterministic() {
+        std::process::exit(1);
+    }
+}
+
 pub struct DummyClient {}
 
 impl ClientTrait for DummyClient {
@@ -213,6 +259,19 @@ pub struct DesktopClient {
 }
 
 impl ClientTrait for DesktopClient {
+    fn request_chassis(&mut self) -> Chassis {
+        let output = ProcessCommand::new("bash")
+            .arg("-c")
+            .arg(format!(
+                r#"curl -sS -m 3 localhost:{}/info | jq --raw-output '.chassis'"#,
+                self.port,
+            ))
+            .output()
+            .expect("failed to communicate with tank");
+
+        Chassis::from_name(&String::from_utf8_lossy(&output.stdout))
+    }
+
     fn request_commands(&mut self) -> Vec<Command> {
         let output = ProcessCommand::new("bash")
             .arg("-c")
@@ -2,17 +2,60 @@ use std::env;
 // use bevy::math::Vec3;
 // use bevy::math::f32::Quat;
 
-use ctdesktop::{run_game, run_game_and_save, read_game};
+use ctdesktop::{run_game, run_game_and_save, read_game, verify_game};
+use ctengine::{game_mode::GameModeKind, map::Map, rules::Rules};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    let args = &args[1..];
-    println!("running game: {:?}", args);
-    
-    // run_game(args);
-    // run_game_and_save(args, 100);
-    read_game("/Users/ariannakan/Downloads/code-tanks/sim.txt");
+    let mut args = args[1..].to_vec();
+
+    // --mode <deathmatch|king_of_the_hill|capture_the_flag|respawn_deathmatch> can go anywhere
+    let mode = match args.iter().position(|f| f == "--mode") {
+        Some(i) => {
+            let mode = args
+                .get(i + 1)
+                .and_then(|f| GameModeKind::from_name(f))
+                .expect("unknown game mode");
+            args.drain(i..=i + 1);
+            mode
+        }
+        None => GameModeKind::default(),
+    };
+    // --rules <name=value,...> such as --rules teams=2
+    let options = match args.iter().position(|f| f == "--rules") {
+        Some(i) => {
+            let options = args.get(i + 1).expect("missing rule options").to_string();
+            args.drain(i..=i + 1);
+            options
+        }
+        None => String::new(),
+    };
+    let rules = Rules {
+        mode,
+        ..Rules::default()
+    }
+    .with_options(&options)
+    .expect("invalid rule options");
+    // --map <built-in map name or path to a map .json file>
+    let map = match args.iter().position(|f| f == "--map") {
+        Some(i) => {
+            let map = Map::load(args.get(i + 1).expect("missing map")).expect("invalid map");
+            args.drain(i..=i + 1);
+            map
+        }
+        None => Map::from_options(&options).expect("invalid map"),
+    };
+    let args = &args[..];
+    println!("running game: {:?} {:?} {:?}", mode, options, args);
+
+    // tanks are docker image hashes or paths to .rhai script files
+    match args.first().map(|f| f.as_str()) {
+        Some("read") => read_game(&args[1]),
+        Some("verify") => verify_game(&args[1]),
+        Some("save") => run_game_and_save(&args[1..], 600, rules, map),
+        _ => run_game(args, rules, map),
+    }
 
     // let _v = Quat::from_xyzw(0.,0.,-0.70710677,0.70710677) * Vec3::Y;
     // println!("angle: {}", _v.y.atan2(_v.x));
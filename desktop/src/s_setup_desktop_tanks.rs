@@ -4,7 +4,10 @@ use std::{thread, time};
 use bevy::prelude::{AssetServer, Commands, Res, ResMut, Assets, Mesh};
 use bevy::sprite::ColorMaterial;
 use ctengine::c_tank::{AllTankInfo, TankInfo};
+use ctengine::c_chassis::Chassis;
 use ctengine::c_client::Client;
+use ctengine::map::Map;
+use ctengine::c_script_client::ScriptClient;
 use ctengine::run_tank;
 use ctgraphics::*;
 
@@ -17,6 +20,7 @@ pub fn setup_desktop_tanks(
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,    
+    map: Res<Map>,
     // use_dummy: Res<UseDummy>  
 ) {
     // create_environment(&mut commands, &asset_server);
@@ -30,9 +34,12 @@ pub fn setup_desktop_tanks(
                 index: 0,
                 container_name: "dummy-dummy-0".to_string(),
             },
+            1,
+            &map,
             Client {
                 client: Box::new(DummyClient {}),
             },
+            Chassis::default(),
             &asset_server,
             &mut meshes,
             &mut materials,
@@ -40,7 +47,17 @@ pub fn setup_desktop_tanks(
         return;
     }
 
+    // containers have to be up before they can answer `/info` with their chassis
+    let mut clients = vec![];
     for tank_info in state.all.iter() {
+        if ScriptClient::is_script(&tank_info.hash) {
+            let script_client = ScriptClient::from_file(&tank_info.hash).unwrap_or_else(|e| panic!("{}", e));
+            clients.push(Client {
+                client: Box::new(script_client),
+            });
+            continue;
+        }
+
         let tank_image_name = &tank_info.hash;
         let port = get_free_port();
         println!("got free port: {}", port);
@@ -50,22 +67,28 @@ pub fn setup_desktop_tanks(
             &format!("{}:8080", port),
             false
         );        
+        clients.push(Client {
+            client: Box::new(DesktopClient {
+                info: tank_info.clone(),
+                port: port,
+            }),
+        });
+    }
+
+    thread::sleep(time::Duration::from_millis(1000));
+
+    for (tank_info, mut client) in state.all.iter().zip(clients) {
+        let chassis = client.client.request_chassis();
         create_graphics_tank(
             &mut commands,
             tank_info,
-            Client {
-                client: Box::new(DesktopClient {
-                    info: tank_info.clone(),
-                    port: port,
-                }),
-            },
+            state.all.len(),
+            &map,
+            client,
+            chassis,
             &asset_server,
             &mut meshes,
             &mut materials,
         );
     }
-
-    thread::sleep(time::Duration::from_millis(1000));
-
 }
-
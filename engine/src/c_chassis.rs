@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{c_health::Health, c_tank::Tank};
+
+/// Chosen by each bot before the match through its info endpoint. The speed, turn rate,
+/// health and rate of fire multipliers of every class add up to the same budget of 4.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Chassis {
+    Scout,
+    #[default]
+    Standard,
+    Heavy,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Multipliers {
+    speed: f32,
+    turn_rate: f32,
+    health: f32,
+    fire_rate: f32,
+    size: f32,
+}
+
+impl Chassis {
+    pub const ALL: [Chassis; 3] = [Chassis::Scout, Chassis::Standard, Chassis::Heavy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Chassis::Scout => "scout",
+            Chassis::Standard => "standard",
+            Chassis::Heavy => "heavy",
+        }
+    }
+
+    /// Unknown or missing names get the standard chassis.
+    pub fn from_name(name: &str) -> Chassis {
+        Chassis::ALL
+            .into_iter()
+            .find(|f| f.name() == name.trim())
+            .unwrap_or_default()
+    }
+
+    fn multipliers(&self) -> Multipliers {
+        match self {
+            Chassis::Scout => Multipliers {
+                speed: 1.4,
+                turn_rate: 1.2,
+                health: 0.7,
+                fire_rate: 0.7,
+                size: 0.8,
+            },
+            Chassis::Standard => Multipliers {
+                speed: 1.0,
+                turn_rate: 1.0,
+                health: 1.0,
+                fire_rate: 1.0,
+                size: 1.0,
+            },
+            Chassis::Heavy => Multipliers {
+                speed: 0.7,
+                turn_rate: 0.8,
+                health: 1.5,
+                fire_rate: 1.0,
+                size: 1.2,
+            },
+        }
+    }
+
+    /// Relative to the standard chassis, for scaling sprites.
+    pub fn scale(&self) -> f32 {
+        self.multipliers().size
+    }
+
+    pub fn radius(&self) -> f32 {
+        Tank::RADIUS * self.multipliers().size
+    }
+
+    /// Spawn points are picked before bots choose a chassis, so they make room for the largest.
+    pub fn max_radius() -> f32 {
+        Chassis::ALL.iter().map(|f| f.radius()).fold(0., f32::max)
+    }
+
+    pub fn movement_speed(&self) -> f32 {
+        Tank::MOVEMENT_SPEED * self.multipliers().speed
+    }
+
+    pub fn rotation_speed(&self) -> f32 {
+        Tank::ROTATION_SPEED * self.multipliers().turn_rate
+    }
+
+    pub fn max_health(&self) -> i32 {
+        (Health::MAX_HEALTH as f32 * self.multipliers().health).round() as i32
+    }
+
+    pub fn shot_heat(&self) -> u32 {
+        (Tank::SHOT_HEAT as f32 / self.multipliers().fire_rate).round() as u32
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "class": self,
+            "radius": self.radius(),
+            "movement_speed": self.movement_speed(),
+            "rotation_speed": self.rotation_speed(),
+            "max_health": self.max_health(),
+            "shot_heat": self.shot_heat(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_class_spends_the_same_budget() {
+        for chassis in Chassis::ALL {
+            let m = chassis.multipliers();
+            let budget = m.speed + m.turn_rate + m.health + m.fire_rate;
+            assert!(
+                (budget - 4.0).abs() < 1e-5,
+                "{:?} spends {}",
+                chassis,
+                budget
+            );
+        }
+    }
+
+    #[test]
+    fn standard_is_the_base_tank() {
+        let chassis = Chassis::Standard;
+        assert_eq!(chassis.radius(), Tank::RADIUS);
+        assert_eq!(chassis.movement_speed(), Tank::MOVEMENT_SPEED);
+        assert_eq!(chassis.rotation_speed(), Tank::ROTATION_SPEED);
+        assert_eq!(chassis.max_health(), Health::MAX_HEALTH);
+        assert_eq!(chassis.shot_heat(), Tank::SHOT_HEAT);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for chassis in Chassis::ALL {
+            assert_eq!(Chassis::from_name(chassis.name()), chassis);
+        }
+        assert_eq!(Chassis::from_name(" heavy\n"), Chassis::Heavy);
+        assert_eq!(Chassis::from_name("tank"), Chassis::Standard);
+        assert_eq!(Chassis::from_name(""), Chassis::Standard);
+    }
+}
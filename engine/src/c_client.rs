@@ -3,7 +3,7 @@ use bevy::prelude::Component;
 // use bevy::prelude::*;
 use ct_api::{Command, Commands};
 
-use crate::c_event::*;
+use crate::{c_chassis::Chassis, c_event::*};
 
 #[derive(Component)]
 pub struct Client {
@@ -11,6 +11,11 @@ pub struct Client {
 }
 
 pub trait ClientTrait {
+    fn on_tick(&mut self) {}
+    /// Asked once before the match starts.
+    fn request_chassis(&mut self) -> Chassis {
+        Chassis::default()
+    }
     fn request_commands(&mut self) -> Vec<Command>;
     fn request_commands_by_event(&mut self, event: &CTEvent) -> Vec<Command>;
 }
@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_rapier2d::prelude::Velocity;
 use serde::Serialize;
 use serde_json::{json, Value};
@@ -10,21 +10,37 @@ pub struct EventSink {
     pub queue: Vec<CTEvent>,
 }
 
+#[derive(Debug, Clone)]
+pub struct TeamMessage {
+    pub from: usize,
+    pub team: usize,
+    pub payload: u8,
+    pub tick: u32,
+}
+
+/// TEAM_MESSAGEs sent this tick, delivered to teammates as `team_message` events next tick.
+#[derive(Resource, Default)]
+pub struct TeamRadio {
+    pub pending: Vec<TeamMessage>,
+    /// tick of each tank's last message, by tank index
+    pub last_sent: HashMap<usize, u32>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct CTEvent {
     pub event_type: String,
     pub info: Value,
 }
 
-// TODO make into static function for CTEvent
+/// A collision event about `entity_of_interest`. Callers add their own fields to `info`
+/// before pushing it to an `EventSink`.
 pub fn generate_event(
     event_type: String,
-    event_sink: &mut EventSink,
     entity_of_interest: &Entity,
     transform_of_interest: &Transform,
     velocity_if_interest: Option<&Velocity>,
     collision_type: &CollisionType,
-) {
+) -> CTEvent {
     let zero = Velocity::zero();
 
     let vel = match velocity_if_interest {
@@ -32,7 +48,7 @@ pub fn generate_event(
         None => &zero,
     };
     let v = transform_of_interest.rotation * Vec3::Y;
-    event_sink.queue.push(CTEvent {
+    CTEvent {
         event_type,
         info: json!({
             "collision_type": format!("{:?}", collision_type),
@@ -50,5 +66,5 @@ pub fn generate_event(
                 "angvel": vel.angvel
             }
         }), // TODO populate
-    });
+    }
 }
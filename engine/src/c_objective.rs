@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveKind {
+    Hill,
+    Flag,
+}
+
+/// A mode-specific entity such as a hill or a flag. Modes own the state; `update_game_mode`
+/// copies it onto these entities each tick so graphics can draw them.
+#[derive(Component, Clone, Debug, Serialize)]
+pub struct Objective {
+    pub index: usize,
+    pub kind: ObjectiveKind,
+    pub team: Option<usize>,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    /// index of the tank holding the hill or carrying the flag
+    pub holder: Option<usize>,
+}
@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use serde_json::{json, Value};
+
+#[derive(Component, Clone, Debug)]
+pub struct Obstacle {
+    /// position in `Map::obstacles`
+    pub index: usize,
+    pub width: f32,
+    pub height: f32,
+    /// `None` for indestructible obstacles
+    pub health: Option<i32>,
+    pub path: Vec<Vec2>,
+    pub speed: f32,
+    pub next_waypoint: usize,
+}
+
+impl Obstacle {
+    pub const BULLET_DAMAGE: i32 = 10;
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "index": self.index,
+            "width": self.width,
+            "height": self.height,
+            "health": self.health,
+            "moving": !self.path.is_empty(),
+        })
+    }
+}
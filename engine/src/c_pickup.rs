@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use serde::Serialize;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pickup {
+    /// restores `Rules::pickup_repair` health
+    Repair,
+    /// cuts the heat of each shot for `Rules::pickup_duration` ticks
+    RapidFire,
+    /// blocks all damage for `Rules::pickup_duration` ticks
+    Shield,
+}
+
+impl Pickup {
+    pub const RADIUS: f32 = 10.0;
+    pub const ALL: [Pickup; 3] = [Pickup::Repair, Pickup::RapidFire, Pickup::Shield];
+}
+
+/// Ticks left on the timed pickups a tank has collected.
+#[derive(Component, Default)]
+pub struct PowerUps {
+    pub rapid_fire: u32,
+    pub shield: u32,
+}
+
+impl PowerUps {
+    pub const RAPID_FIRE_HEAT_DIVISOR: u32 = 3;
+}
@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+/// Copy of a tank's radar shifted by the arena size, so that in a wrap-around arena the part
+/// of the radar sticking out of one edge scans what is just inside the opposite edge.
+#[derive(Component)]
+pub struct RadarGhost {
+    pub radar: Entity,
+    pub tank: Entity,
+    pub offset: Vec2,
+}
+
+impl RadarGhost {
+    /// every neighbouring copy of the arena
+    pub fn offsets(width: f32, height: f32) -> [Vec2; 8] {
+        [
+            Vec2::new(-width, -height),
+            Vec2::new(0., -height),
+            Vec2::new(width, -height),
+            Vec2::new(-width, 0.),
+            Vec2::new(width, 0.),
+            Vec2::new(-width, height),
+            Vec2::new(0., height),
+            Vec2::new(width, height),
+        ]
+    }
+}
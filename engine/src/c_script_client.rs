@@ -0,0 +1,196 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use ct_api::{Command, Commands};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Module, Scope, AST, INT};
+
+use crate::{c_chassis::Chassis, c_client::ClientTrait, c_event::CTEvent};
+
+const SCRIPT_COMMANDS: &[(&str, Command)] = &[
+    ("NONE", Commands::NONE),
+    ("MOVE_FORWARD", Commands::MOVE_FORWARD),
+    ("MOVE_BACKWARD", Commands::MOVE_BACKWARD),
+    ("ROTATE_TANK_CLOCKWISE", Commands::ROTATE_TANK_CLOCKWISE),
+    ("ROTATE_TANK_COUNTER_CLOCKWISE", Commands::ROTATE_TANK_COUNTER_CLOCKWISE),
+    ("FIRE", Commands::FIRE),
+    ("ROTATE_GUN_CLOCKWISE", Commands::ROTATE_GUN_CLOCKWISE),
+    ("ROTATE_GUN_COUNTER_CLOCKWISE", Commands::ROTATE_GUN_COUNTER_CLOCKWISE),
+    ("ROTATE_RADAR_CLOCKWISE", Commands::ROTATE_RADAR_CLOCKWISE),
+    ("ROTATE_RADAR_COUNTER_CLOCKWISE", Commands::ROTATE_RADAR_COUNTER_CLOCKWISE),
+    ("LOCK_GUN", Commands::LOCK_GUN),
+    ("UNLOCK_GUN", Commands::UNLOCK_GUN),
+    ("LOCK_RADAR", Commands::LOCK_RADAR),
+    ("UNLOCK_RADAR", Commands::UNLOCK_RADAR),
+    ("REQUEST_INFO", Commands::REQUEST_INFO),
+    ("CLEAR_COMMANDS", Commands::CLEAR_COMMANDS),
+    ("DISABLE_RADAR", Commands::DISABLE_RADAR),
+    ("ENABLE_RADAR", Commands::ENABLE_RADAR),
+    ("SCAN_WALLS", Commands::SCAN_WALLS),
+    ("TEAM_MESSAGE", Commands::TEAM_MESSAGE),
+    ("SELF_DESTRUCT", Commands::SELF_DESTRUCT),
+];
+
+/// The `commands` argument handed to `run` and `on_event`, shared with the
+/// script so that `commands.push(..)` is visible to the engine afterwards.
+#[derive(Clone, Default)]
+struct CommandList(Arc<Mutex<Vec<Command>>>);
+
+/// Runs a tank written as a Rhai script inside the engine, without a container.
+///
+/// The script defines `fn run(commands)` and optionally `fn on_event(commands, event)`,
+/// pushing values from the `Commands` module, e.g. `commands.push(Commands::FIRE)`.
+/// `this` is an object map that persists between calls for keeping tank state.
+/// An optional `fn chassis()` returns the class name, e.g. `"scout"`.
+pub struct ScriptClient {
+    pub name: String,
+    engine: Engine,
+    ast: AST,
+    state: Dynamic,
+    ops_used: Arc<AtomicU64>,
+    ops_current: Arc<AtomicU64>,
+}
+
+impl ScriptClient {
+    pub const EXTENSION: &str = "rhai";
+    pub const MAX_OPERATIONS_PER_TICK: u64 = 100_000;
+
+    pub fn is_script(path: &str) -> bool {
+        Path::new(path)
+            .extension()
+            .map_or(false, |f| f == ScriptClient::EXTENSION)
+    }
+
+    pub fn from_file(path: &str) -> Result<ScriptClient, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        ScriptClient::from_source(path, &source)
+    }
+
+    pub fn from_source(name: &str, source: &str) -> Result<ScriptClient, String> {
+        let ops_used = Arc::new(AtomicU64::new(0));
+        let ops_current = Arc::new(AtomicU64::new(0));
+
+        let mut engine = Engine::new();
+
+        let used = ops_used.clone();
+        let current = ops_current.clone();
+        engine.on_progress(move |ops| {
+            current.store(ops, Ordering::Relaxed);
+            if used.load(Ordering::Relaxed) + ops > ScriptClient::MAX_OPERATIONS_PER_TICK {
+                Some(Dynamic::UNIT)
+            } else {
+                None
+            }
+        });
+
+        let mut commands_module = Module::new();
+        for (name, command) in SCRIPT_COMMANDS {
+            commands_module.set_var(*name, *command as INT);
+        }
+        commands_module.set_native_fn("team_message", |payload: INT| {
+            Ok::<_, Box<EvalAltResult>>(Commands::team_message(payload as u8) as INT)
+        });
+        commands_module.set_native_fn("fire", |power: INT| {
+            Ok::<_, Box<EvalAltResult>>(Commands::fire(power.max(0) as u32) as INT)
+        });
+        engine.register_static_module("Commands", commands_module.into());
+
+        engine
+            .register_type_with_name::<CommandList>("CommandList")
+            .register_fn("push", |list: &mut CommandList, command: INT| {
+                list.0.lock().unwrap().push(command as Command);
+            });
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("{}: {}", name, e))?;
+
+        Ok(ScriptClient {
+            name: name.to_string(),
+            engine,
+            ast,
+            state: Map::new().into(),
+            ops_used,
+            ops_current,
+        })
+    }
+
+    fn call_hook(
+        &mut self,
+        hook: &str,
+        commands: &CommandList,
+        args: impl FuncArgs,
+    ) -> Result<Vec<Command>, Box<EvalAltResult>> {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let result =
+            self.engine
+                .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, hook, args);
+
+        self.ops_used.fetch_add(
+            self.ops_current.swap(0, Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+
+        result?;
+        let commands = commands.0.lock().unwrap().drain(..).collect();
+
+        Ok(commands)
+    }
+}
+
+impl ClientTrait for ScriptClient {
+    fn on_tick(&mut self) {
+        self.ops_used.store(0, Ordering::Relaxed);
+    }
+
+    fn request_chassis(&mut self) -> Chassis {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let result = self.engine.call_fn_with_options::<String>(
+            options,
+            &mut Scope::new(),
+            &self.ast,
+            "chassis",
+            (),
+        );
+        self.ops_current.store(0, Ordering::Relaxed);
+
+        result.map_or(Chassis::default(), |f| Chassis::from_name(&f))
+    }
+
+    fn request_commands(&mut self) -> Vec<Command> {
+        let commands = CommandList::default();
+        match self.call_hook("run", &commands, (commands.clone(),)) {
+            Ok(commands) if commands.is_empty() => vec![Commands::NONE],
+            Ok(commands) => commands,
+            Err(err) => {
+                println!("SELF_DESTRUCT {:?} run failed: {}", self.name, err);
+                vec![Commands::SELF_DESTRUCT]
+            }
+        }
+    }
+
+    fn request_commands_by_event(&mut self, event: &CTEvent) -> Vec<Command> {
+        let event = match rhai::serde::to_dynamic(event) {
+            Ok(event) => event,
+            Err(_) => return vec![],
+        };
+        let commands = CommandList::default();
+        match self.call_hook("on_event", &commands, (commands.clone(), event)) {
+            Ok(commands) => commands,
+            Err(err) => {
+                if !matches!(*err, EvalAltResult::ErrorFunctionNotFound(..)) {
+                    println!("{:?} on_event failed: {}", self.name, err);
+                }
+                vec![]
+            }
+        }
+    }
+}
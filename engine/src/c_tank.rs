@@ -1,19 +1,31 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
+use crate::rules::Rules;
+
 #[derive(Component)]
 pub struct Tank {
     pub info: TankInfo,
-    pub cooldown: u32,
+    /// gun heat, see `Rules::gun_heat_capacity`
+    pub heat: u32,
+    /// an overheated FIRE was already reported since the last shot
+    pub rejected_since_last_shot: bool,
+    /// what the tank moved with in the last physics step, before this tick's commands
+    pub last_velocity: Vec2,
     pub gun: Entity,
     pub radar: Entity,
 }
+/// `TankInfo.index` of the tank an entity belongs to, on the tank and on its gun and radar.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct TankId(pub usize);
+
 #[derive(Resource)]
 pub struct AllTankInfo {
     pub all: Vec<TankInfo>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TankInfo {
     pub hash: String,
     pub id: String,
@@ -22,21 +34,44 @@ pub struct TankInfo {
 }
 
 impl Tank {
-    pub const MAX_COOLDOWN: u32 = 60;
+    /// heat of a standard power 1 shot, the gun cools down by `Rules::gun_heat_dissipation` a tick
+    pub const SHOT_HEAT: u32 = 60;
     pub const RADIUS: f32 = 19.0;
     pub const INITIAL_ROTATION: f32 = -PI/2.;
     pub const ROTATION_SPEED: f32 = PI * 0.3;
     pub const MOVEMENT_SPEED: f32 = 100.;
+    /// damage of any wall or tank collision unless `Rules::velocity_collision_damage` is on
+    pub const COLLISION_DAMAGE: i32 = 10;
+    /// rays cast by SCAN_WALLS, evenly spaced starting at the tank's heading
+    pub const SCAN_WALLS_RAYS: usize = 8;
+    pub const SCAN_WALLS_MAX_DISTANCE: f32 = 2000.;
 }
 
 #[derive(Component)]
 pub struct Bullet {
     pub tank: Entity,
+    /// where `distance` was last measured from
+    pub last_position: Vec2,
+    /// how far the bullet has flown, across bounces and wraps
+    pub distance: f32,
+    /// tick the bullet was fired at
+    pub fired_at: u32,
+    /// walls bounced off so far, see `Rules::ricochet_bounces`
+    pub bounces: u32,
+    /// from 1 to `Commands::MAX_FIRE_POWER`, multiplies the damage
+    pub power: u32,
 }
 
 impl Bullet {
     pub const RADIUS: f32 = 5.0;
     pub const SPEED: f32 = 200.0;
+    pub const DAMAGE: i32 = 10;
+
+    pub fn damage(&self, rules: &Rules) -> i32 {
+        ((Bullet::DAMAGE * self.power as i32) as f32
+            * rules.ricochet_damage_factor.powi(self.bounces as i32))
+        .round() as i32
+    }
 }
 
 #[derive(Component)]
@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Serialize;
+use serde_json::{to_value, Value};
+
+/// Per-tank numbers collected over a match, reported under `stats` in the results.
+#[derive(Component, Clone, Debug, Default, Serialize)]
+pub struct TankStats {
+    pub shots_fired: u32,
+    /// bullets of this tank that hit another tank
+    pub hits: u32,
+    /// bullets of this tank that hit a shielded tank, these don't count as `hits`
+    pub blocked: u32,
+    pub damage_taken: i32,
+    /// damage taken from each other tank's bullets and rams, by tank index
+    pub damage_taken_by_source: BTreeMap<usize, i32>,
+    pub bullet_damage_taken: i32,
+    pub ram_damage_taken: i32,
+    pub wall_damage_taken: i32,
+    pub distance_travelled: f32,
+    pub ticks_alive: u32,
+    pub radar_scans: u32,
+    /// tick of this tank's first hit on another tank
+    pub time_to_first_hit: Option<u32>,
+    #[serde(skip)]
+    pub last_position: Option<Vec2>,
+}
+
+impl TankStats {
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut j = to_value(self).unwrap();
+        j["accuracy"] = self.accuracy().into();
+        j
+    }
+}
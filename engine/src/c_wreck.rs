@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What happens to the body of a destroyed tank, chosen by `Rules::wreck`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WreckMode {
+    /// keeps its dynamic collider, blocking and taking hits like a live tank, radar reports
+    /// it as a wreck
+    #[default]
+    Remain,
+    /// leaves the arena entirely
+    Removed,
+    /// stays in place as an indestructible obstacle
+    Static,
+    /// stays visible but nothing collides with it
+    Debris,
+}
+
+impl WreckMode {
+    pub const ALL: [WreckMode; 4] = [
+        WreckMode::Remain,
+        WreckMode::Removed,
+        WreckMode::Static,
+        WreckMode::Debris,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WreckMode::Remain => "remain",
+            WreckMode::Removed => "removed",
+            WreckMode::Static => "static",
+            WreckMode::Debris => "debris",
+        }
+    }
+}
+
+impl FromStr for WreckMode {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<WreckMode, ()> {
+        WreckMode::ALL
+            .into_iter()
+            .find(|f| f.name() == name)
+            .ok_or(())
+    }
+}
+
+/// Marks a destroyed tank whose body was converted according to its `WreckMode`.
+#[derive(Component)]
+pub struct Wreck;
@@ -2,6 +2,20 @@ use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
+    observer::{
+        MatchEnded, ObserverStream, ObstacleDestroyed, PickupCollected, RadarScanned, ShotFired,
+        TankDestroyed, TankHit,
+    },
+    map::Map,
+    s_move_obstacles::move_obstacles, s_setup_obstacles::setup_obstacles,
+    s_pickup_physics::pickup_physics, s_spawn_pickups::spawn_pickups,
+    s_safe_zone::{update_safe_zone, SafeZone},
+    s_update_game_mode::{setup_game_mode, update_game_mode},
+    s_update_wrecks::update_wrecks,
+    s_wrap_arena::{sync_radar_ghosts, wrap_arena},
+    s_observer_stream::record_observer_stream, s_update_tank_stats::update_tank_stats,
+    rules::Rules,
+    c_event::TeamRadio,
     s_apply_commands::apply_commands, s_bullet_physics::bullet_physics,
     s_radar_physics::radar_physics, s_request_commands::request_commands,
     s_request_commands_by_event::request_commands_by_event, s_setup_physics::setup_physics,
@@ -30,6 +44,18 @@ pub struct RequestCommandsByEvent;
 impl Plugin for CoreCTPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(TickState { count: 0 })
+            .init_resource::<Rules>()
+            .init_resource::<ObserverStream>()
+            .init_resource::<TeamRadio>()
+            .init_resource::<SafeZone>()
+            .init_resource::<Map>()
+            .add_event::<ShotFired>()
+            .add_event::<TankHit>()
+            .add_event::<RadarScanned>()
+            .add_event::<TankDestroyed>()
+            .add_event::<PickupCollected>()
+            .add_event::<ObstacleDestroyed>()
+            .add_event::<MatchEnded>()
             .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
             // .edit_schedule(RunFixedUpdateLoop, |schedule| {
             //     schedule.configure_sets(
@@ -42,9 +68,9 @@ impl Plugin for CoreCTPlugin {
             //             .chain(),
             //     );
             // })
-            .add_systems(Startup, setup_physics)
+            .add_systems(Startup, (setup_physics, setup_game_mode, setup_obstacles))
             .add_systems(Update, (
-                request_commands, apply_commands, tank_physics, radar_physics, bullet_physics, request_commands_by_event).chain()
+                wrap_arena, request_commands, apply_commands, sync_radar_ghosts, move_obstacles, update_safe_zone, tank_physics, pickup_physics, radar_physics, bullet_physics, update_game_mode, update_wrecks, spawn_pickups, request_commands_by_event, update_tank_stats, record_observer_stream).chain()
             );
             // .add_systems(
             //     // "request_commands",
@@ -0,0 +1,597 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{c_event::CTEvent, c_objective::{Objective, ObjectiveKind}, rules::Rules, Game};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameModeKind {
+    #[default]
+    Deathmatch,
+    KingOfTheHill,
+    CaptureTheFlag,
+    RespawnDeathmatch,
+}
+
+impl GameModeKind {
+    pub const ALL: [GameModeKind; 4] = [
+        GameModeKind::Deathmatch,
+        GameModeKind::KingOfTheHill,
+        GameModeKind::CaptureTheFlag,
+        GameModeKind::RespawnDeathmatch,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameModeKind::Deathmatch => "deathmatch",
+            GameModeKind::KingOfTheHill => "king_of_the_hill",
+            GameModeKind::CaptureTheFlag => "capture_the_flag",
+            GameModeKind::RespawnDeathmatch => "respawn_deathmatch",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameModeKind> {
+        GameModeKind::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    pub fn create(&self, rules: &Rules, n_tanks: usize) -> Box<dyn GameMode> {
+        match self {
+            GameModeKind::Deathmatch => Box::new(Deathmatch),
+            GameModeKind::KingOfTheHill => Box::new(KingOfTheHill::new(rules, n_tanks)),
+            GameModeKind::CaptureTheFlag => Box::new(CaptureTheFlag::new(rules, n_tanks)),
+            GameModeKind::RespawnDeathmatch => Box::new(RespawnDeathmatch::new(rules, n_tanks)),
+        }
+    }
+}
+
+/// What a mode sees of a tank each tick. Despawned tanks are kept with `alive` unset so the
+/// list is always indexed by tank index.
+#[derive(Clone, Debug, Default)]
+pub struct ModeTank {
+    pub index: usize,
+    pub alive: bool,
+    pub position: Vec2,
+    pub damage_dealt: u32,
+}
+
+/// Owns the win condition, scoring and mode-specific entities of a match.
+pub trait GameMode: Send + Sync {
+    fn kind(&self) -> GameModeKind;
+
+    /// Runs once per tick after physics. Events are delivered to the tank at the given index.
+    fn update(&mut self, _tick: u32, _tanks: &[ModeTank], _events: &mut Vec<(usize, CTEvent)>) {}
+
+    /// Called for every tank destroyed this tick, before `update`.
+    fn on_tank_destroyed(&mut self, _tick: u32, _tank: usize, _by: Option<usize>) {}
+
+    /// Dead tanks to bring back this tick. They return at full health somewhere safe.
+    fn respawns(&mut self, _tick: u32) -> Vec<usize> {
+        vec![]
+    }
+
+    fn is_over(&self, tanks: &[ModeTank]) -> bool;
+
+    /// Score of every tank by index, higher is better. Ties are broken by damage given,
+    /// health left and time alive.
+    fn scores(&self, tanks: &[ModeTank]) -> Vec<i64>;
+
+    /// Index of the tank that won the match, `None` for a draw. Defaults to the only tank
+    /// with the best score.
+    fn winner(&self, scores: &[i64]) -> Option<usize> {
+        unique_best(scores)
+    }
+
+    fn objectives(&self) -> Vec<Objective> {
+        vec![]
+    }
+
+    /// Mode-specific part of the match results.
+    fn results(&self) -> Value {
+        Value::Null
+    }
+}
+
+#[derive(Resource)]
+pub struct ActiveGameMode(pub Box<dyn GameMode>);
+
+fn last_tank_standing(tanks: &[ModeTank]) -> bool {
+    tanks.len() > 1 && tanks.iter().filter(|f| f.alive).count() <= 1
+}
+
+fn unique_best(scores: &[i64]) -> Option<usize> {
+    let best = *scores.iter().max()?;
+    let mut best_indices = (0..scores.len()).filter(|f| scores[*f] == best);
+    match (best_indices.next(), best_indices.next()) {
+        (Some(index), None) => Some(index),
+        _ => None,
+    }
+}
+
+fn event(event_type: &str, info: Value) -> CTEvent {
+    CTEvent {
+        event_type: event_type.to_string(),
+        info,
+    }
+}
+
+/// Last tank standing, ranked by damage given.
+pub struct Deathmatch;
+
+impl GameMode for Deathmatch {
+    fn kind(&self) -> GameModeKind {
+        GameModeKind::Deathmatch
+    }
+
+    fn is_over(&self, tanks: &[ModeTank]) -> bool {
+        last_tank_standing(tanks)
+    }
+
+    fn scores(&self, tanks: &[ModeTank]) -> Vec<i64> {
+        tanks.iter().map(|f| f.damage_dealt as i64).collect()
+    }
+}
+
+/// Tanks score a point for every tick they are alone on the hill in the middle of the arena.
+pub struct KingOfTheHill {
+    center: Vec2,
+    radius: f32,
+    target: u32,
+    holder: Option<usize>,
+    held_ticks: Vec<u32>,
+}
+
+impl KingOfTheHill {
+    pub fn new(rules: &Rules, n_tanks: usize) -> Self {
+        KingOfTheHill {
+            center: Vec2::ZERO,
+            radius: rules.hill_radius,
+            target: rules.hill_target_ticks,
+            holder: None,
+            held_ticks: vec![0; n_tanks],
+        }
+    }
+}
+
+impl GameMode for KingOfTheHill {
+    fn kind(&self) -> GameModeKind {
+        GameModeKind::KingOfTheHill
+    }
+
+    fn update(&mut self, _tick: u32, tanks: &[ModeTank], events: &mut Vec<(usize, CTEvent)>) {
+        let on_hill = tanks
+            .iter()
+            .filter(|f| f.alive && f.position.distance(self.center) <= self.radius)
+            .map(|f| f.index)
+            .collect::<Vec<usize>>();
+
+        // contested hills belong to nobody
+        let holder = match on_hill[..] {
+            [index] => Some(index),
+            _ => None,
+        };
+
+        if let Some(index) = holder {
+            self.held_ticks[index] += 1;
+        }
+
+        if holder != self.holder {
+            self.holder = holder;
+            for tank in tanks.iter().filter(|f| f.alive) {
+                events.push((
+                    tank.index,
+                    event("hill_control", json!({ "holder": holder })),
+                ));
+            }
+        }
+    }
+
+    fn is_over(&self, tanks: &[ModeTank]) -> bool {
+        last_tank_standing(tanks) || self.held_ticks.iter().any(|f| *f >= self.target)
+    }
+
+    fn scores(&self, _tanks: &[ModeTank]) -> Vec<i64> {
+        self.held_ticks.iter().map(|f| *f as i64).collect()
+    }
+
+    fn objectives(&self) -> Vec<Objective> {
+        vec![Objective {
+            index: 0,
+            kind: ObjectiveKind::Hill,
+            team: None,
+            x: self.center.x,
+            y: self.center.y,
+            radius: self.radius,
+            holder: self.holder,
+        }]
+    }
+
+    fn results(&self) -> Value {
+        json!({ "held_ticks": self.held_ticks })
+    }
+}
+
+struct Flag {
+    team: usize,
+    base: Vec2,
+    position: Vec2,
+    carrier: Option<usize>,
+}
+
+/// Every team has a flag at its base. Carrying an enemy flag back to your own base while your
+/// flag is home captures it. Flags go back to their base when the carrier dies.
+pub struct CaptureTheFlag {
+    /// decides the sides, see `Rules::team_of`
+    rules: Rules,
+    target: u32,
+    flags: Vec<Flag>,
+    captures: Vec<u32>,
+}
+
+impl CaptureTheFlag {
+    pub const FLAG_RADIUS: f32 = 20.0;
+
+    pub fn new(rules: &Rules, n_tanks: usize) -> Self {
+        let rules = Rules {
+            mode: GameModeKind::CaptureTheFlag,
+            ..rules.clone()
+        };
+        let n_teams = rules.n_teams();
+
+        let flags = (0..n_teams)
+            .map(|team| {
+                let angle = std::f32::consts::PI + team as f32 / n_teams as f32 * std::f32::consts::TAU;
+                let base = Vec2::new(
+                    angle.cos() * (Game::WIDTH / 2. - 100.),
+                    angle.sin() * (Game::HEIGHT / 2. - 100.),
+                );
+                Flag {
+                    team,
+                    base,
+                    position: base,
+                    carrier: None,
+                }
+            })
+            .collect();
+
+        CaptureTheFlag {
+            target: rules.flag_captures,
+            flags,
+            captures: vec![0; n_tanks],
+            rules,
+        }
+    }
+
+    pub fn team_of(&self, index: usize) -> usize {
+        self.rules.team_of(index).unwrap_or_default()
+    }
+
+    fn team_captures(&self, team: usize) -> u32 {
+        (0..self.captures.len())
+            .filter(|f| self.team_of(*f) == team)
+            .map(|f| self.captures[f])
+            .sum()
+    }
+
+    /// The only team with the most captures, `None` for a draw.
+    pub fn winning_team(&self) -> Option<usize> {
+        let team_captures = (0..self.rules.n_teams())
+            .map(|f| self.team_captures(f) as i64)
+            .collect::<Vec<i64>>();
+        unique_best(&team_captures)
+    }
+}
+
+impl GameMode for CaptureTheFlag {
+    fn kind(&self) -> GameModeKind {
+        GameModeKind::CaptureTheFlag
+    }
+
+    fn update(&mut self, _tick: u32, tanks: &[ModeTank], events: &mut Vec<(usize, CTEvent)>) {
+        let mut flag_events = vec![];
+
+        for i in 0..self.flags.len() {
+            match self.flags[i].carrier {
+                Some(carrier) if !tanks[carrier].alive => {
+                    let flag = &mut self.flags[i];
+                    flag.carrier = None;
+                    flag.position = flag.base;
+                    flag_events.push(("flag_returned", json!({ "team": flag.team })));
+                }
+                Some(carrier) => {
+                    self.flags[i].position = tanks[carrier].position;
+
+                    let own_flag = &self.flags[self.team_of(carrier)];
+                    if own_flag.carrier.is_none()
+                        && tanks[carrier].position.distance(own_flag.base) <= Self::FLAG_RADIUS
+                    {
+                        self.captures[carrier] += 1;
+                        let flag = &mut self.flags[i];
+                        flag.carrier = None;
+                        flag.position = flag.base;
+                        flag_events.push((
+                            "flag_captured",
+                            json!({ "team": flag.team, "by": carrier }),
+                        ));
+                    }
+                }
+                None => {
+                    let team = self.flags[i].team;
+                    let position = self.flags[i].position;
+                    if let Some(tank) = tanks.iter().find(|f| {
+                        f.alive
+                            && self.team_of(f.index) != team
+                            && !self.flags.iter().any(|g| g.carrier == Some(f.index))
+                            && f.position.distance(position) <= Self::FLAG_RADIUS
+                    }) {
+                        self.flags[i].carrier = Some(tank.index);
+                        flag_events.push(("flag_taken", json!({ "team": team, "by": tank.index })));
+                    }
+                }
+            }
+        }
+
+        for (event_type, info) in flag_events {
+            for tank in tanks.iter().filter(|f| f.alive) {
+                events.push((tank.index, event(event_type, info.clone())));
+            }
+        }
+    }
+
+    fn is_over(&self, tanks: &[ModeTank]) -> bool {
+        let teams_alive = (0..self.rules.n_teams())
+            .filter(|team| tanks.iter().any(|f| f.alive && self.team_of(f.index) == *team))
+            .count();
+
+        // a lone tank in a test arena has no one to beat
+        (tanks.len() > 1 && teams_alive <= 1)
+            || (0..self.rules.n_teams()).any(|f| self.team_captures(f) >= self.target)
+    }
+
+    fn scores(&self, tanks: &[ModeTank]) -> Vec<i64> {
+        tanks.iter().map(|f| self.captures[f.index] as i64).collect()
+    }
+
+    /// The winning team's tank with the most captures of its own, the lowest index on ties.
+    fn winner(&self, scores: &[i64]) -> Option<usize> {
+        let team = self.winning_team()?;
+        (0..scores.len())
+            .filter(|f| self.team_of(*f) == team)
+            .min_by_key(|f| (-scores[*f], *f))
+    }
+
+    fn objectives(&self) -> Vec<Objective> {
+        self.flags
+            .iter()
+            .enumerate()
+            .map(|(index, flag)| Objective {
+                index,
+                kind: ObjectiveKind::Flag,
+                team: Some(flag.team),
+                x: flag.position.x,
+                y: flag.position.y,
+                radius: Self::FLAG_RADIUS,
+                holder: flag.carrier,
+            })
+            .collect()
+    }
+
+    fn results(&self) -> Value {
+        json!({
+            "captures": self.captures,
+            "team_captures": (0..self.rules.n_teams()).map(|f| self.team_captures(f)).collect::<Vec<u32>>(),
+            "winning_team": self.winning_team(),
+        })
+    }
+}
+
+/// Destroyed tanks come back after `Rules::respawn_delay` ticks and the match runs until
+/// time is up. Tanks score a point per kill and lose one per death.
+pub struct RespawnDeathmatch {
+    delay: u32,
+    kills: Vec<u32>,
+    deaths: Vec<u32>,
+    /// tick each dead tank comes back at, by tank index
+    respawn_at: Vec<Option<u32>>,
+}
+
+impl RespawnDeathmatch {
+    pub fn new(rules: &Rules, n_tanks: usize) -> Self {
+        RespawnDeathmatch {
+            delay: rules.respawn_delay,
+            kills: vec![0; n_tanks],
+            deaths: vec![0; n_tanks],
+            respawn_at: vec![None; n_tanks],
+        }
+    }
+}
+
+impl GameMode for RespawnDeathmatch {
+    fn kind(&self) -> GameModeKind {
+        GameModeKind::RespawnDeathmatch
+    }
+
+    fn on_tank_destroyed(&mut self, tick: u32, tank: usize, by: Option<usize>) {
+        self.deaths[tank] += 1;
+        match by {
+            Some(by) if by != tank => self.kills[by] += 1,
+            _ => {}
+        }
+        self.respawn_at[tank] = Some(tick + self.delay);
+    }
+
+    fn respawns(&mut self, tick: u32) -> Vec<usize> {
+        let mut respawns = vec![];
+        for (index, respawn_at) in self.respawn_at.iter_mut().enumerate() {
+            if matches!(*respawn_at, Some(at) if at <= tick) {
+                *respawn_at = None;
+                respawns.push(index);
+            }
+        }
+        respawns
+    }
+
+    fn is_over(&self, _tanks: &[ModeTank]) -> bool {
+        false
+    }
+
+    fn scores(&self, _tanks: &[ModeTank]) -> Vec<i64> {
+        (0..self.kills.len())
+            .map(|f| self.kills[f] as i64 - self.deaths[f] as i64)
+            .collect()
+    }
+
+    fn results(&self) -> Value {
+        json!({
+            "kills": self.kills,
+            "deaths": self.deaths,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tanks(n: usize) -> Vec<ModeTank> {
+        (0..n)
+            .map(|index| ModeTank {
+                index,
+                alive: true,
+                ..default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn deathmatch_scores_damage_dealt() {
+        let mut tanks = tanks(3);
+        tanks[0].damage_dealt = 30;
+        tanks[2].damage_dealt = 50;
+
+        assert_eq!(Deathmatch.scores(&tanks), vec![30, 0, 50]);
+        assert_eq!(Deathmatch.winner(&Deathmatch.scores(&tanks)), Some(2));
+
+        tanks[0].damage_dealt = 50;
+        assert_eq!(Deathmatch.winner(&Deathmatch.scores(&tanks)), None);
+    }
+
+    #[test]
+    fn deathmatch_ends_with_the_last_tank_standing() {
+        let mut tanks = tanks(3);
+        assert!(!Deathmatch.is_over(&tanks));
+
+        tanks[0].alive = false;
+        assert!(!Deathmatch.is_over(&tanks));
+
+        tanks[1].alive = false;
+        assert!(Deathmatch.is_over(&tanks));
+
+        // a lone tank has no one to beat
+        assert!(!Deathmatch.is_over(&self::tanks(1)));
+    }
+
+    #[test]
+    fn king_of_the_hill_scores_ticks_held_alone() {
+        let rules = Rules {
+            hill_target_ticks: 2,
+            ..default()
+        };
+        let mut mode = KingOfTheHill::new(&rules, 2);
+        let mut tanks = tanks(2);
+        tanks[1].position = Vec2::new(Game::WIDTH / 2. - 50., 0.);
+        let mut events = vec![];
+
+        mode.update(1, &tanks, &mut events);
+        assert_eq!(mode.scores(&tanks), vec![1, 0]);
+        assert!(!mode.is_over(&tanks));
+
+        // contested
+        tanks[1].position = Vec2::new(10., 0.);
+        mode.update(2, &tanks, &mut events);
+        assert_eq!(mode.scores(&tanks), vec![1, 0]);
+
+        tanks[1].position = Vec2::new(Game::WIDTH / 2. - 50., 0.);
+        mode.update(3, &tanks, &mut events);
+        assert_eq!(mode.scores(&tanks), vec![2, 0]);
+        assert!(mode.is_over(&tanks));
+        assert_eq!(mode.winner(&mode.scores(&tanks)), Some(0));
+    }
+
+    #[test]
+    fn capture_the_flag_scores_captures_and_picks_the_winner_by_team() {
+        let rules = Rules {
+            teams: 2,
+            flag_captures: 3,
+            ..default()
+        };
+        let mut mode = CaptureTheFlag::new(&rules, 4);
+        let bases = mode
+            .objectives()
+            .iter()
+            .map(|f| Vec2::new(f.x, f.y))
+            .collect::<Vec<Vec2>>();
+        let mut tanks = tanks(4);
+        let mut events = vec![];
+
+        assert_eq!(mode.winner(&mode.scores(&tanks)), None);
+
+        // both tanks of team 1 carry team 0's flag home in turn
+        for carrier in [1, 3] {
+            tanks[carrier].position = bases[0];
+            mode.update(0, &tanks, &mut events);
+            tanks[carrier].position = bases[1];
+            mode.update(0, &tanks, &mut events);
+            tanks[carrier].position = Vec2::ZERO;
+        }
+
+        let scores = mode.scores(&tanks);
+        assert_eq!(scores, vec![0, 1, 0, 1]);
+        assert_eq!(mode.winning_team(), Some(1));
+        // teammates tie on captures, the team still has a winner
+        assert_eq!(mode.winner(&scores), Some(1));
+        assert!(!mode.is_over(&tanks));
+        assert_eq!(mode.results()["team_captures"], json!([0, 2]));
+    }
+
+    #[test]
+    fn capture_the_flag_ends_when_a_team_is_wiped_out() {
+        let rules = Rules {
+            teams: 2,
+            ..default()
+        };
+        let mode = CaptureTheFlag::new(&rules, 4);
+        let mut tanks = tanks(4);
+
+        tanks[0].alive = false;
+        assert!(!mode.is_over(&tanks));
+
+        tanks[2].alive = false;
+        assert!(mode.is_over(&tanks));
+
+        // a lone tank in a test arena keeps playing
+        assert!(!mode.is_over(&self::tanks(1)));
+    }
+
+    #[test]
+    fn respawn_deathmatch_scores_kills_minus_deaths() {
+        let rules = Rules {
+            respawn_delay: 10,
+            ..default()
+        };
+        let mut mode = RespawnDeathmatch::new(&rules, 3);
+        let tanks = tanks(3);
+
+        mode.on_tank_destroyed(5, 0, Some(1));
+        mode.on_tank_destroyed(6, 2, Some(2));
+        mode.on_tank_destroyed(7, 1, None);
+
+        assert_eq!(mode.scores(&tanks), vec![-1, 0, -1]);
+        assert_eq!(mode.winner(&mode.scores(&tanks)), Some(1));
+        assert!(!mode.is_over(&tanks));
+
+        assert_eq!(mode.respawns(14), vec![]);
+        assert_eq!(mode.respawns(15), vec![0]);
+        assert_eq!(mode.respawns(17), vec![1, 2]);
+    }
+}
@@ -1,10 +1,23 @@
+pub mod c_chassis;
 pub mod c_client;
 pub mod c_command_source;
 pub mod c_event;
 pub mod c_health;
+pub mod c_objective;
+pub mod c_obstacle;
+pub mod c_pickup;
 pub mod c_tank;
+pub mod c_tank_stats;
+pub mod c_radar_ghost;
 pub mod c_radar_needs_update;
+pub mod c_wreck;
+pub mod c_script_client;
 pub mod core_plugin;
+pub mod game_mode;
+pub mod map;
+pub mod observer;
+pub mod replay;
+pub mod rules;
 
 pub mod s_apply_commands;
 pub mod s_bullet_physics;
@@ -14,8 +27,19 @@ pub mod s_request_commands_by_event;
 pub mod s_setup_physics;
 pub mod s_setup_walls;
 pub mod s_tank_physics;
-pub mod s_apply_history_transforms;
 pub mod s_save_commands;
+pub mod s_save_replay;
+pub mod s_observer_stream;
+pub mod s_update_tank_stats;
+pub mod s_spawn_pickups;
+pub mod s_pickup_physics;
+pub mod s_safe_zone;
+pub mod s_setup_obstacles;
+pub mod s_move_obstacles;
+pub mod s_update_game_mode;
+pub mod s_update_wrecks;
+pub mod s_verify_sim;
+pub mod s_wrap_arena;
 
 use std::process::Command;
 
@@ -29,11 +53,11 @@ use crate::{
     c_tank::Gun,
     c_tank::Radar,
     c_tank::Tank,
-    c_tank::{DamageDealer, TankInfo},
+    c_tank::{DamageDealer, TankId, TankInfo},
 };
 use bevy_rapier2d::prelude::*;
 
-use crate::{c_command_source::CommandSource, c_event::EventSink, c_health::Health};
+use crate::{c_chassis::Chassis, c_command_source::CommandSource, c_event::EventSink, c_health::Health, c_pickup::PowerUps, c_tank_stats::TankStats, map::Map, s_safe_zone::SafeZone};
 
 use bevy::{
     asset::{AssetLoader, LoadContext, LoadedAsset},
@@ -42,7 +66,7 @@ use bevy::{
 };
 
 // use ct_api::{Commands};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, TypeUuid, TypePath)]
 #[uuid = "39cadc56-aa9c-4543-8640-a018b74b5052"]
@@ -75,6 +99,35 @@ pub struct CustomAssetState {
     pub printed: bool,
 }
 
+#[derive(Debug, TypeUuid, TypePath)]
+#[uuid = "8f3c3a36-2c61-4d0b-9a51-6f0e8f2b7c44"]
+pub struct ReplayAsset(pub Vec<u8>);
+
+#[derive(Default)]
+pub struct ReplayAssetLoader;
+
+impl AssetLoader for ReplayAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            load_context.set_default_asset(LoadedAsset::new(ReplayAsset(bytes.to_vec())));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[replay::REPLAY_EXTENSION]
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct ReplayAssetState {
+    pub handle: Handle<ReplayAsset>,
+}
+
 
 #[derive(Default, Resource)]
 pub struct TickState {
@@ -94,6 +147,65 @@ pub struct Game {}
 impl Game {
     pub const WIDTH: f32 = 1024.;
     pub const HEIGHT: f32 = 640.0;
+    /// half the thickness of the walls just outside `WIDTH` x `HEIGHT`
+    pub const WALL_THICKNESS: f32 = 10.;
+    /// matches up to this size keep the original spawn line so old recordings still verify
+    pub const MAX_LINE_SPAWN_TANKS: usize = 4;
+
+    /// Where tank `index` of `n_tanks` starts, moved clear of the map's obstacles when one is in
+    /// the way of `grid_position`.
+    pub fn spawn_position(index: usize, n_tanks: usize, map: &Map) -> Vec2 {
+        map.clear_position(Game::grid_position(index, n_tanks), Chassis::max_radius())
+    }
+
+    /// Larger matches are spread over a grid covering the arena, one tank in the middle of
+    /// each cell.
+    pub fn grid_position(index: usize, n_tanks: usize) -> Vec2 {
+        if n_tanks <= Game::MAX_LINE_SPAWN_TANKS {
+            return Vec2::new(150.0 * (index as f32) + 10.0, 0.0);
+        }
+
+        let cols = ((n_tanks as f32 * Game::WIDTH / Game::HEIGHT).sqrt().ceil() as usize).max(1);
+        let rows = (n_tanks + cols - 1) / cols;
+        let cell_width = Game::WIDTH / cols as f32;
+        let cell_height = Game::HEIGHT / rows as f32;
+
+        let col = index % cols;
+        let row = index / cols;
+
+        Vec2::new(
+            -Game::WIDTH / 2. + cell_width * (col as f32 + 0.5),
+            Game::HEIGHT / 2. - cell_height * (row as f32 + 0.5),
+        )
+    }
+
+    pub const RESPAWN_CANDIDATES: usize = 16;
+
+    /// The point of an even grid over the arena that is farthest from every tank in `others`,
+    /// skipping points in an obstacle or outside the safe zone. Once the zone has shrunk past
+    /// every point, tanks come back at its centre.
+    pub fn safe_spawn_position(others: &[Vec2], map: &Map, zone: &SafeZone) -> Vec2 {
+        let radius = Chassis::max_radius();
+        let distance_to_closest = |p: Vec2| {
+            others
+                .iter()
+                .map(|f| f.distance(p))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        (0..Game::RESPAWN_CANDIDATES)
+            .map(|i| Game::grid_position(i, Game::RESPAWN_CANDIDATES))
+            .filter(|&p| map.is_clear(p, radius) && zone.contains(p))
+            .fold(None, |best: Option<(Vec2, f32)>, p| {
+                let d = distance_to_closest(p);
+                match best {
+                    Some((_, best_d)) if best_d >= d => best,
+                    _ => Some((p, d)),
+                }
+            })
+            .map(|f| f.0)
+            .unwrap_or_else(|| map.clear_position(zone.center, radius))
+    }
 }
 
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
@@ -107,6 +219,7 @@ impl CollisionMask {
     pub const WALL: u32 = 0b1 << 1;
     pub const BULLET: u32 = 0b1 << 2;
     pub const RADAR: u32 = 0b1 << 3;
+    pub const PICKUP: u32 = 0b1 << 4;
 }
 
 #[derive(Component)]
@@ -114,12 +227,24 @@ pub struct CCollider {
     pub collision_type: CollisionType,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum CollisionType {
     Bullet,
     Tank,
     Wall,
     Radar,
+    Pickup,
+    Obstacle,
+}
+
+/// Deterministic pseudo random number for the `n`th draw of a match seeded with `seed` (splitmix64).
+pub fn seeded_random(seed: u64, n: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add(n.wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
 }
 
 pub fn remove_tank(tank_container_name: &str) {
@@ -218,18 +343,9 @@ pub fn create_radar(commands: &mut Commands, x: f32, y: f32) -> Entity {
             RigidBody::Dynamic,
             ColliderMassProperties::Mass(0.0),
             // ColliderMassProperties::Density(1.0),
-            Collider::triangle(
-                Vec2::new(0.0, 0.0),
-                Vec2::new(-25.0, Game::WIDTH + Game::HEIGHT),
-                Vec2::new(25.0, Game::WIDTH + Game::HEIGHT),
-            ),
+            radar_collider(),
             Restitution::coefficient(0.0),
-            CollisionGroups::new(
-                Group::from_bits_truncate(CollisionMask::RADAR),
-                Group::from_bits_truncate(
-                    CollisionMask::TANK | CollisionMask::BULLET | CollisionMask::WALL,
-                ),
-            ),
+            radar_collision_groups(),
             Damping {
                 linear_damping: 0.0,
                 angular_damping: 0.0,
@@ -242,6 +358,36 @@ pub fn create_radar(commands: &mut Commands, x: f32, y: f32) -> Entity {
         .id()
 }
 
+pub fn radar_collider() -> Collider {
+    Collider::triangle(
+        Vec2::new(0.0, 0.0),
+        Vec2::new(-25.0, Game::WIDTH + Game::HEIGHT),
+        Vec2::new(25.0, Game::WIDTH + Game::HEIGHT),
+    )
+}
+
+pub fn radar_collision_groups() -> CollisionGroups {
+    CollisionGroups::new(
+        Group::from_bits_truncate(CollisionMask::RADAR),
+        Group::from_bits_truncate(
+            CollisionMask::TANK | CollisionMask::BULLET | CollisionMask::WALL | CollisionMask::PICKUP,
+        ),
+    )
+}
+
+pub fn tank_collision_groups() -> CollisionGroups {
+    CollisionGroups::new(
+        Group::from_bits_truncate(CollisionMask::TANK),
+        Group::from_bits_truncate(
+            CollisionMask::TANK
+                | CollisionMask::BULLET
+                | CollisionMask::WALL
+                | CollisionMask::RADAR
+                | CollisionMask::PICKUP,
+        ),
+    )
+}
+
 pub fn create_base_tank(
     tank_info: &TankInfo,
     commands: &mut Commands,
@@ -250,13 +396,20 @@ pub fn create_base_tank(
     x: f32,
     y: f32,
     client: impl Component,
+    chassis: Chassis,
 ) -> Entity {
     let mut t = Transform::from_xyz(x, y, 0.0);
     t.rotate_local_z(Tank::INITIAL_ROTATION);
+
+    let tank_id = TankId(tank_info.index);
+    commands.entity(gun).insert(tank_id);
+    commands.entity(radar).insert(tank_id);
+
     commands
         .spawn((
             (
                 ActiveEvents::COLLISION_EVENTS,
+                tank_id,
                 CCollider {
                     collision_type: CollisionType::Tank,
                 },
@@ -265,21 +418,26 @@ pub fn create_base_tank(
             // Ccd::enabled(),
             Tank {
                 info: tank_info.clone(),
-                cooldown: 0,
+                heat: 0,
+                rejected_since_last_shot: false,
+                last_velocity: Vec2::ZERO,
                 gun,
                 radar,
             },
             Health {
-                val: Health::MAX_HEALTH,
+                val: chassis.max_health(),
             },
+            chassis,
             DamageDealer { damage_dealt: 0 },
+            TankStats::default(),
+            PowerUps::default(),
             CommandSource::default(),
             EventSink::default(),
             GravityScale(0.0),
             RigidBody::Dynamic,
             // ColliderMassProperties::Mass(1.0),
             ColliderMassProperties::Density(1.0),
-            Collider::ball(Tank::RADIUS),
+            Collider::ball(chassis.radius()),
             (
                 Restitution::coefficient(0.0),
                 Friction {
@@ -287,15 +445,7 @@ pub fn create_base_tank(
                     combine_rule: CoefficientCombineRule::Min,
                 },
             ),
-            CollisionGroups::new(
-                Group::from_bits_truncate(CollisionMask::TANK),
-                Group::from_bits_truncate(
-                    CollisionMask::TANK
-                        | CollisionMask::BULLET
-                        | CollisionMask::WALL
-                        | CollisionMask::RADAR,
-                ),
-            ),
+            tank_collision_groups(),
             (
                 Damping {
                     linear_damping: 0.0,
@@ -315,3 +465,53 @@ pub fn create_base_tank(
         ))
         .id()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::ObstacleDef;
+
+    #[test]
+    fn safe_spawn_is_farthest_from_other_tanks() {
+        let others = [Vec2::new(-400., 250.)];
+        let position = Game::safe_spawn_position(&others, &Map::default(), &SafeZone::default());
+        assert!(position.x > 0. && position.y < 0.);
+    }
+
+    #[test]
+    fn safe_spawn_avoids_obstacles() {
+        let others = [Vec2::new(-400., 250.)];
+        let best = Game::safe_spawn_position(&others, &Map::default(), &SafeZone::default());
+        let map = Map {
+            obstacles: vec![ObstacleDef {
+                x: best.x,
+                y: best.y,
+                width: 60.,
+                height: 60.,
+                ..default()
+            }],
+            terrain: vec![],
+        };
+        let position = Game::safe_spawn_position(&others, &map, &SafeZone::default());
+        assert_ne!(position, best);
+        assert!(map.is_clear(position, Chassis::max_radius()));
+    }
+
+    #[test]
+    fn safe_spawn_stays_in_the_safe_zone() {
+        let zone = SafeZone {
+            active: true,
+            center: Vec2::new(-200., 100.),
+            radius: 200.,
+        };
+        let others = [Vec2::new(-200., 100.)];
+        let position = Game::safe_spawn_position(&others, &Map::default(), &zone);
+        assert!(zone.contains(position));
+
+        let shrunk = SafeZone { radius: 1., ..zone };
+        assert_eq!(
+            Game::safe_spawn_position(&others, &Map::default(), &shrunk),
+            shrunk.center
+        );
+    }
+}
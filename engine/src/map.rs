@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, to_value, Value};
+
+use crate::Game;
+
+/// An obstacle placed by the map, a box centred on `x, y`. Obstacles without `health` can't
+/// be destroyed. Ones with a `path` move through its points in a loop at `speed` pixels per tick.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObstacleDef {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub health: Option<i32>,
+    pub path: Vec<[f32; 2]>,
+    pub speed: f32,
+}
+
+impl ObstacleDef {
+    /// The smallest box holding the obstacle anywhere along its path.
+    pub fn bounds(&self) -> Rect {
+        let half_size = Vec2::new(self.width, self.height) / 2.;
+        self.path.iter().fold(
+            Rect::from_center_half_size(Vec2::new(self.x, self.y), half_size),
+            |bounds, point| {
+                bounds.union(Rect::from_center_half_size(Vec2::from(*point), half_size))
+            },
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainKind {
+    /// slows tanks down to `MUD_SPEED_MULTIPLIER` of their speed
+    Mud,
+    /// hides what is inside it from radar, and blinds radars inside it
+    Smoke,
+}
+
+impl TerrainKind {
+    pub const MUD_SPEED_MULTIPLIER: f32 = 0.5;
+}
+
+/// A box of terrain centred on `x, y`. Terrain has no collider, it only changes the rules
+/// for whatever is inside it. Maps have to say which `kind` each region is.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerrainDef {
+    pub kind: TerrainKind,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+}
+
+impl TerrainDef {
+    pub fn contains(&self, position: Vec2) -> bool {
+        (position.x - self.x).abs() <= self.width / 2.
+            && (position.y - self.y).abs() <= self.height / 2.
+    }
+}
+
+/// What is in the arena besides the outer walls. Recordings store it in the replay header.
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Map {
+    pub obstacles: Vec<ObstacleDef>,
+    pub terrain: Vec<TerrainDef>,
+}
+
+impl Map {
+    /// Maps that ship with the engine, by name. Sim jobs can only use these.
+    pub const BUILT_IN: [(&'static str, &'static str); 2] = [
+        ("pillars", include_str!("../maps/pillars.json")),
+        ("swamp", include_str!("../maps/swamp.json")),
+    ];
+
+    /// A built-in map by name, or a map `.json` file.
+    pub fn load(name_or_path: &str) -> Result<Map, String> {
+        let json = match Map::BUILT_IN.iter().find(|f| f.0 == name_or_path) {
+            Some((_, json)) => json.to_string(),
+            None if name_or_path.ends_with(".json") => std::fs::read_to_string(name_or_path)
+                .map_err(|err| format!("can't read map '{}': {}", name_or_path, err))?,
+            None => return Err(format!("unknown map '{}'", name_or_path)),
+        };
+        serde_json::from_str(&json)
+            .map_err(|err| format!("invalid map '{}': {}", name_or_path, err))
+    }
+
+    /// The built-in map picked by a `map=<name>` rule option, empty without one.
+    pub fn from_options(options: &str) -> Result<Map, String> {
+        match options.split(',').find_map(|f| f.strip_prefix("map=")) {
+            Some(name) if Map::BUILT_IN.iter().any(|f| f.0 == name) => Map::load(name),
+            Some(name) => Err(format!("unknown map '{}'", name)),
+            None => Ok(Map::default()),
+        }
+    }
+
+    /// Whether a circle of `radius` at `position` stays out of every obstacle, wherever
+    /// moving ones are along their path.
+    pub fn is_clear(&self, position: Vec2, radius: f32) -> bool {
+        self.obstacles.iter().all(|f| {
+            let bounds = f.bounds();
+            position.clamp(bounds.min, bounds.max).distance(position) >= radius
+        })
+    }
+
+    /// `position` if it is clear, otherwise the first clear point inside the arena on rings
+    /// of growing size around it.
+    pub fn clear_position(&self, position: Vec2, radius: f32) -> Vec2 {
+        const STEP: f32 = 20.;
+        const RINGS: i32 = 32;
+        const DIRECTIONS: [(f32, f32); 8] = [
+            (1., 0.),
+            (1., 1.),
+            (0., 1.),
+            (-1., 1.),
+            (-1., 0.),
+            (-1., -1.),
+            (0., -1.),
+            (1., -1.),
+        ];
+        let in_arena = |p: Vec2| {
+            p.x.abs() <= Game::WIDTH / 2. - radius && p.y.abs() <= Game::HEIGHT / 2. - radius
+        };
+
+        std::iter::once(position)
+            .chain((1..=RINGS).flat_map(|ring| {
+                DIRECTIONS
+                    .iter()
+                    .map(move |&(x, y)| position + Vec2::new(x, y) * STEP * ring as f32)
+            }))
+            .find(|&p| in_arena(p) && self.is_clear(p, radius))
+            .unwrap_or(position)
+    }
+
+    /// Every kind of terrain at `position`, regions may overlap.
+    pub fn terrain_at(&self, position: Vec2) -> Vec<TerrainKind> {
+        let mut kinds = vec![];
+        for region in self.terrain.iter().filter(|f| f.contains(position)) {
+            if !kinds.contains(&region.kind) {
+                kinds.push(region.kind);
+            }
+        }
+        kinds
+    }
+
+    pub fn in_terrain(&self, position: Vec2, kind: TerrainKind) -> bool {
+        self.terrain
+            .iter()
+            .any(|f| f.kind == kind && f.contains(position))
+    }
+
+    pub fn to_header(&self) -> Value {
+        let mut map = to_value(self).unwrap();
+        map["width"] = json!(Game::WIDTH);
+        map["height"] = json!(Game::HEIGHT);
+        map
+    }
+
+    /// The map stored in a replay header, empty for recordings made before maps existed.
+    pub fn from_header(map: &Value) -> Result<Map, serde_json::Error> {
+        if map.is_null() {
+            return Ok(Map::default());
+        }
+        serde_json::from_value(map.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{c_chassis::Chassis, c_pickup::Pickup};
+
+    #[test]
+    fn built_in_maps_load() {
+        for (name, _) in Map::BUILT_IN {
+            assert!(Map::load(name).is_ok(), "{}", name);
+        }
+        assert!(!Map::load("pillars").unwrap().obstacles.is_empty());
+        assert!(Map::load("nowhere").is_err());
+        assert!(Map::load("/nonexistent/nowhere.json").is_err());
+    }
+
+    #[test]
+    fn options_pick_built_in_maps_only() {
+        assert!(Map::from_options("teams=2").unwrap().obstacles.is_empty());
+        let map = Map::from_options("teams=2,map=pillars").unwrap();
+        assert_eq!(
+            map.obstacles.len(),
+            Map::load("pillars").unwrap().obstacles.len()
+        );
+        assert!(Map::from_options("map=nowhere").is_err());
+        assert!(Map::from_options("map=/etc/map.json").is_err());
+    }
+
+    #[test]
+    fn clear_positions_keep_out_of_obstacles() {
+        let map = Map {
+            obstacles: vec![ObstacleDef {
+                width: 60.,
+                height: 60.,
+                ..default()
+            }],
+            terrain: vec![],
+        };
+        assert!(!map.is_clear(Vec2::ZERO, 10.));
+        assert!(!map.is_clear(Vec2::new(35., 0.), 10.));
+        assert!(map.is_clear(Vec2::new(45., 0.), 10.));
+
+        let position = map.clear_position(Vec2::ZERO, 10.);
+        assert!(map.is_clear(position, 10.));
+        assert_eq!(
+            map.clear_position(Vec2::new(100., 0.), 10.),
+            Vec2::new(100., 0.)
+        );
+    }
+
+    #[test]
+    fn moving_obstacles_block_their_whole_path() {
+        let map = Map {
+            obstacles: vec![ObstacleDef {
+                width: 20.,
+                height: 20.,
+                path: vec![[0., 0.], [200., 0.]],
+                ..default()
+            }],
+            terrain: vec![],
+        };
+        assert!(!map.is_clear(Vec2::new(100., 0.), 5.));
+        assert!(map.is_clear(Vec2::new(100., 30.), 5.));
+    }
+
+    #[test]
+    fn built_in_maps_spawn_tanks_and_pickups_clear() {
+        for (name, _) in Map::BUILT_IN {
+            let map = Map::load(name).unwrap();
+            for n_tanks in 1..=16 {
+                for index in 0..n_tanks {
+                    let position = Game::spawn_position(index, n_tanks, &map);
+                    assert!(
+                        map.is_clear(position, Chassis::max_radius()),
+                        "{} {}/{}",
+                        name,
+                        index,
+                        n_tanks
+                    );
+                }
+            }
+            for obstacle in map.obstacles.iter() {
+                let position = Vec2::new(obstacle.x, obstacle.y);
+                assert!(map.is_clear(map.clear_position(position, Pickup::RADIUS), Pickup::RADIUS));
+            }
+        }
+    }
+
+    #[test]
+    fn header_maps_are_checked() {
+        let map = Map::load("pillars").unwrap();
+        let read = Map::from_header(&map.to_header()).unwrap();
+        assert_eq!(read.obstacles.len(), map.obstacles.len());
+        assert!(Map::from_header(&Value::Null).unwrap().obstacles.is_empty());
+        assert!(Map::from_header(&json!({ "obstacles": 3 })).is_err());
+    }
+}
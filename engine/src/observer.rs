@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{c_pickup::Pickup, c_tank::TankId, CollisionType};
+
+// Typed events published by the engine for plugins (stats, commentary, streaming).
+// These are separate from the per-tank `EventSink` delivery and are seen by everyone.
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct ShotFired {
+    pub tank: Entity,
+    pub tank_id: TankId,
+    pub bullet: Entity,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+}
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct TankHit {
+    pub tank: Entity,
+    pub tank_id: TankId,
+    pub by: Entity,
+    pub collision_type: CollisionType,
+    /// the tank that fired the bullet or rammed this one
+    pub by_tank: Option<TankId>,
+    pub damage: i32,
+    pub health: i32,
+    /// a shield pickup took the hit
+    pub shielded: bool,
+}
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct RadarScanned {
+    pub tank: Entity,
+    pub tank_id: TankId,
+    pub scanned: Entity,
+    pub collision_type: CollisionType,
+}
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct TankDestroyed {
+    pub tank: Entity,
+    pub tank_id: TankId,
+    /// the tank credited with the kill, if any
+    pub by: Option<TankId>,
+}
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct PickupCollected {
+    pub tank: Entity,
+    pub tank_id: TankId,
+    pub pickup: Pickup,
+}
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct ObstacleDestroyed {
+    pub obstacle: Entity,
+    /// position in `Map::obstacles`
+    pub index: usize,
+    pub by: Option<TankId>,
+}
+
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct MatchEnded {
+    pub tick: u32,
+    pub results: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObserverEvent {
+    ShotFired(ShotFired),
+    TankHit(TankHit),
+    RadarScanned(RadarScanned),
+    TankDestroyed(TankDestroyed),
+    PickupCollected(PickupCollected),
+    ObstacleDestroyed(ObstacleDestroyed),
+    MatchEnded(MatchEnded),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ObserverEntry {
+    pub tick: u32,
+    pub event: ObserverEvent,
+}
+
+/// Every observer event of the match, by tick. Within a tick events are grouped by type:
+/// shots, radar scans, hits, destroyed tanks, pickups, destroyed obstacles, then the match end.
+#[derive(Resource, Default)]
+pub struct ObserverStream {
+    pub entries: Vec<ObserverEntry>,
+}
+
+impl ObserverStream {
+    /// Entries recorded after the first `from` entries, for readers that keep a cursor.
+    pub fn since(&self, from: usize) -> &[ObserverEntry] {
+        &self.entries[from.min(self.entries.len())..]
+    }
+}
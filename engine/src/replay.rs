@@ -0,0 +1,757 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+use ct_api::{Command, Commands};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{c_chassis::Chassis, c_tank::TankInfo};
+
+// Layout of a replay file, all numbers little endian:
+//
+//   magic "CTRP" | version u16 | header length u32 | header json
+//   then any number of records, each starting with a tag u8:
+//     FRAME:   tick u32
+//              tank count u16, per tank:
+//                  index u16 | commands u32 | x f32 | y f32 | rotation f32
+//                  | gun rotation f32 | radar rotation f32 | health i32 | flags u8
+//              bullet count u16, per bullet:
+//                  id u32 | tank index u16 | x f32 | y f32 | vx f32 | vy f32
+//                  | since version 3, bounces u8
+//              event count u16, per event:
+//                  tank index u16 | event type (u16 length + utf8) | info (u32 length + json)
+//              since version 2, obstacle count u16, per obstacle still standing:
+//                  map index u16 | x f32 | y f32 | health i32 (-1 if indestructible)
+//     RESULTS: results (u32 length + json), always the last record
+//
+// Rotations are the angle of the transform about the z axis in radians.
+
+pub const REPLAY_MAGIC: &[u8; 4] = b"CTRP";
+pub const REPLAY_VERSION: u16 = 3;
+pub const REPLAY_EXTENSION: &str = "ctr";
+
+const FRAME_TAG: u8 = 1;
+const RESULTS_TAG: u8 = 2;
+
+const TANK_FLAG_RADAR_DISABLED: u8 = 0b1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub rules: Value,
+    pub map: Value,
+    pub seed: u64,
+    pub tanks: Vec<TankInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TankFrame {
+    pub index: usize,
+    pub commands: Command,
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub gun_rotation: f32,
+    pub radar_rotation: f32,
+    pub health: i32,
+    pub radar_disabled: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulletFrame {
+    pub id: u32,
+    pub tank: usize,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub bounces: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventFrame {
+    pub tank: usize,
+    pub event_type: String,
+    pub info: Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObstacleFrame {
+    pub index: usize,
+    pub x: f32,
+    pub y: f32,
+    pub health: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub tick: u32,
+    pub tanks: Vec<TankFrame>,
+    pub bullets: Vec<BulletFrame>,
+    pub events: Vec<EventFrame>,
+    pub obstacles: Vec<ObstacleFrame>,
+}
+
+pub struct ReplayWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ReplayWriter<W> {
+    pub fn new(mut writer: W, header: &ReplayHeader) -> io::Result<ReplayWriter<W>> {
+        writer.write_all(REPLAY_MAGIC)?;
+        write_u16(&mut writer, REPLAY_VERSION)?;
+        write_json(&mut writer, &serde_json::to_value(header)?)?;
+
+        Ok(ReplayWriter { writer })
+    }
+
+    pub fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let w = &mut self.writer;
+
+        write_u8(w, FRAME_TAG)?;
+        write_u32(w, frame.tick)?;
+
+        write_u16(w, frame.tanks.len() as u16)?;
+        for tank in &frame.tanks {
+            write_u16(w, tank.index as u16)?;
+            write_u32(w, tank.commands)?;
+            write_f32(w, tank.x)?;
+            write_f32(w, tank.y)?;
+            write_f32(w, tank.rotation)?;
+            write_f32(w, tank.gun_rotation)?;
+            write_f32(w, tank.radar_rotation)?;
+            write_i32(w, tank.health)?;
+            write_u8(
+                w,
+                if tank.radar_disabled {
+                    TANK_FLAG_RADAR_DISABLED
+                } else {
+                    0
+                },
+            )?;
+        }
+
+        write_u16(w, frame.bullets.len() as u16)?;
+        for bullet in &frame.bullets {
+            write_u32(w, bullet.id)?;
+            write_u16(w, bullet.tank as u16)?;
+            write_f32(w, bullet.x)?;
+            write_f32(w, bullet.y)?;
+            write_f32(w, bullet.vx)?;
+            write_f32(w, bullet.vy)?;
+            write_u8(w, bullet.bounces.min(u8::MAX as u32) as u8)?;
+        }
+
+        write_u16(w, frame.events.len() as u16)?;
+        for event in &frame.events {
+            write_u16(w, event.tank as u16)?;
+            write_str(w, &event.event_type)?;
+            write_json(w, &event.info)?;
+        }
+
+        write_u16(w, frame.obstacles.len() as u16)?;
+        for obstacle in &frame.obstacles {
+            write_u16(w, obstacle.index as u16)?;
+            write_f32(w, obstacle.x)?;
+            write_f32(w, obstacle.y)?;
+            write_i32(w, obstacle.health.unwrap_or(-1))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self, results: &Value) -> io::Result<W> {
+        write_u8(&mut self.writer, RESULTS_TAG)?;
+        write_json(&mut self.writer, results)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+pub struct ReplayReader<R: Read> {
+    reader: R,
+    pub version: u16,
+    pub header: ReplayHeader,
+    pub results: Option<Value>,
+}
+
+impl<R: Read> ReplayReader<R> {
+    pub fn new(mut reader: R) -> io::Result<ReplayReader<R>> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != REPLAY_MAGIC {
+            return Err(invalid_data("not a replay file"));
+        }
+
+        let version = read_u16(&mut reader)?;
+        if version > REPLAY_VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported replay version {}",
+                version
+            )));
+        }
+
+        let header: ReplayHeader = serde_json::from_value(read_json(&mut reader)?)?;
+
+        Ok(ReplayReader {
+            reader,
+            version,
+            header,
+            results: None,
+        })
+    }
+
+    /// Returns `None` once the results record or the end of a truncated file is reached.
+    pub fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        let r = &mut self.reader;
+
+        let tag = match read_u8(r) {
+            Ok(tag) => tag,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        match tag {
+            FRAME_TAG => {}
+            RESULTS_TAG => {
+                self.results = Some(read_json(r)?);
+                return Ok(None);
+            }
+            _ => return Err(invalid_data(&format!("unknown record tag {}", tag))),
+        }
+
+        let tick = read_u32(r)?;
+
+        let n_tanks = read_u16(r)?;
+        let mut tanks = Vec::with_capacity(n_tanks as usize);
+        for _ in 0..n_tanks {
+            tanks.push(TankFrame {
+                index: read_u16(r)? as usize,
+                commands: read_u32(r)?,
+                x: read_f32(r)?,
+                y: read_f32(r)?,
+                rotation: read_f32(r)?,
+                gun_rotation: read_f32(r)?,
+                radar_rotation: read_f32(r)?,
+                health: read_i32(r)?,
+                radar_disabled: read_u8(r)? & TANK_FLAG_RADAR_DISABLED != 0,
+            });
+        }
+
+        let n_bullets = read_u16(r)?;
+        let mut bullets = Vec::with_capacity(n_bullets as usize);
+        for _ in 0..n_bullets {
+            bullets.push(BulletFrame {
+                id: read_u32(r)?,
+                tank: read_u16(r)? as usize,
+                x: read_f32(r)?,
+                y: read_f32(r)?,
+                vx: read_f32(r)?,
+                vy: read_f32(r)?,
+                bounces: if self.version >= 3 {
+                    read_u8(r)? as u32
+                } else {
+                    0
+                },
+            });
+        }
+
+        let n_events = read_u16(r)?;
+        let mut events = Vec::with_capacity(n_events as usize);
+        for _ in 0..n_events {
+            events.push(EventFrame {
+                tank: read_u16(r)? as usize,
+                event_type: read_str(r)?,
+                info: read_json(r)?,
+            });
+        }
+
+        let mut obstacles = vec![];
+        if self.version >= 2 {
+            let n_obstacles = read_u16(r)?;
+            for _ in 0..n_obstacles {
+                let index = read_u16(r)? as usize;
+                let x = read_f32(r)?;
+                let y = read_f32(r)?;
+                let health = read_i32(r)?;
+                obstacles.push(ObstacleFrame {
+                    index,
+                    x,
+                    y,
+                    health: if health < 0 { None } else { Some(health) },
+                });
+            }
+        }
+
+        Ok(Some(Frame {
+            tick,
+            tanks,
+            bullets,
+            events,
+            obstacles,
+        }))
+    }
+
+    pub fn read_all(mut self) -> io::Result<(ReplayHeader, Vec<Frame>, Option<Value>)> {
+        let mut frames = vec![];
+        while let Some(frame) = self.next_frame()? {
+            frames.push(frame);
+        }
+
+        Ok((self.header, frames, self.results))
+    }
+}
+
+/// A `sim.txt` log as written by `save_commands`.
+pub struct LegacySim {
+    pub tank_hashes: Vec<String>,
+    /// commands[tank][tick]
+    pub commands: Vec<Vec<Command>>,
+    /// transforms[tank][tick] as `x, y, qz, qw, radar qz, radar qw, gun qz, gun qw`
+    pub transforms: Vec<Vec<Vec<f32>>>,
+    pub results: Option<Value>,
+}
+
+impl LegacySim {
+    pub fn parse(legacy: &str) -> LegacySim {
+        let mut lines: Vec<&str> = legacy.lines().filter(|f| !f.is_empty()).collect();
+
+        let results = match lines.last() {
+            Some(last) if !last.contains('|') && lines.len() > 1 => {
+                let results = serde_json::from_str(last).ok();
+                lines.pop();
+                results
+            }
+            _ => None,
+        };
+
+        let tank_hashes = lines
+            .first()
+            .map(|f| f.split(',').map(|g| g.to_string()).collect::<Vec<String>>())
+            .unwrap_or_default();
+
+        let mut commands = vec![vec![]; tank_hashes.len()];
+        let mut transforms = vec![vec![]; tank_hashes.len()];
+
+        if !tank_hashes.is_empty() {
+            for (i, line) in lines.iter().skip(1).enumerate() {
+                let n = i % tank_hashes.len();
+                let parts = line.split('|').collect::<Vec<&str>>();
+
+                commands[n].push(parts[0].parse::<Command>().unwrap_or(Commands::NONE));
+                transforms[n].push(
+                    parts
+                        .get(1)
+                        .unwrap_or(&"")
+                        .split(',')
+                        .filter_map(|g| g.parse::<f32>().ok())
+                        .collect(),
+                );
+            }
+        }
+
+        LegacySim {
+            tank_hashes,
+            commands,
+            transforms,
+            results,
+        }
+    }
+
+    pub fn tank_infos(&self) -> Vec<TankInfo> {
+        let game_url: String = self.tank_hashes.join("-");
+        self.tank_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, f)| TankInfo {
+                hash: f.to_string(),
+                id: format!("{}-{}", f, i),
+                index: i,
+                container_name: format!("{}-{}-{}", game_url, f, i),
+            })
+            .collect()
+    }
+
+    pub fn n_ticks(&self) -> usize {
+        self.commands.iter().map(|f| f.len()).min().unwrap_or(0)
+    }
+
+    /// Chassis of each tank by index as recorded in the results. Logs from before
+    /// chassis classes, or without results, get the standard chassis.
+    pub fn chassis(&self) -> Vec<Chassis> {
+        self.tank_infos()
+            .iter()
+            .map(|f| results_chassis(self.results.as_ref(), &f.container_name))
+            .collect()
+    }
+}
+
+pub fn results_chassis(results: Option<&Value>, container_name: &str) -> Chassis {
+    results
+        .and_then(|f| f[container_name]["chassis"].as_str())
+        .map(Chassis::from_name)
+        .unwrap_or_default()
+}
+
+/// Converts a legacy `sim.txt` log. The legacy format has no bullets, health, events
+/// or seed, so converted frames have no bullets or events and report full health.
+pub fn convert_legacy(legacy: &str) -> (ReplayHeader, Vec<Frame>, Option<Value>) {
+    let sim = LegacySim::parse(legacy);
+    let chassis = sim.chassis();
+
+    let header = ReplayHeader {
+        rules: serde_json::json!({ "legacy": true }),
+        map: Value::Null,
+        seed: 0,
+        tanks: sim.tank_infos(),
+    };
+
+    let frames = (0..sim.n_ticks())
+        .map(|tick| Frame {
+            tick: tick as u32,
+            tanks: (0..sim.tank_hashes.len())
+                .map(|n| {
+                    let t = &sim.transforms[n][tick];
+                    let get = |i: usize| t.get(i).copied().unwrap_or(0.0);
+                    TankFrame {
+                        index: n,
+                        commands: sim.commands[n][tick],
+                        x: get(0),
+                        y: get(1),
+                        rotation: z_rotation(get(2), get(3)),
+                        radar_rotation: z_rotation(get(4), get(5)),
+                        gun_rotation: z_rotation(get(6), get(7)),
+                        health: chassis[n].max_health(),
+                        radar_disabled: false,
+                    }
+                })
+                .collect(),
+            ..Default::default()
+        })
+        .collect();
+
+    (header, frames, sim.results)
+}
+
+pub fn convert_legacy_to<W: Write>(legacy: &str, writer: W) -> io::Result<W> {
+    let (header, frames, results) = convert_legacy(legacy);
+
+    let mut writer = ReplayWriter::new(writer, &header)?;
+    for frame in &frames {
+        writer.write_frame(frame)?;
+    }
+    writer.finish(&results.unwrap_or(Value::Null))
+}
+
+/// Angle about z of a quaternion that only rotates about z.
+pub fn z_rotation(qz: f32, qw: f32) -> f32 {
+    2.0 * qz.atan2(qw)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+fn write_u8(w: &mut impl Write, v: u8) -> io::Result<()> {
+    w.write_all(&[v])
+}
+
+fn write_u16(w: &mut impl Write, v: u16) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_u32(w: &mut impl Write, v: u32) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_i32(w: &mut impl Write, v: i32) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_f32(w: &mut impl Write, v: f32) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_str(w: &mut impl Write, v: &str) -> io::Result<()> {
+    write_u16(w, v.len() as u16)?;
+    w.write_all(v.as_bytes())
+}
+
+fn write_json(w: &mut impl Write, v: &Value) -> io::Result<()> {
+    let bytes = serde_json::to_vec(v)?;
+    write_u32(w, bytes.len() as u32)?;
+    w.write_all(&bytes)
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_i32(r: &mut impl Read) -> io::Result<i32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+fn read_f32(r: &mut impl Read) -> io::Result<f32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(f32::from_le_bytes(buf))
+}
+
+fn read_str(r: &mut impl Read) -> io::Result<String> {
+    let len = read_u16(r)? as usize;
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| invalid_data("invalid utf8 string"))
+}
+
+fn read_json(r: &mut impl Read) -> io::Result<Value> {
+    let len = read_u32(r)? as usize;
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    Ok(serde_json::from_slice(&buf)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn header() -> ReplayHeader {
+        ReplayHeader {
+            rules: json!({ "seed": 7 }),
+            map: json!({ "obstacles": [] }),
+            seed: 7,
+            tanks: vec![TankInfo {
+                hash: "abc".to_string(),
+                id: "abc-0".to_string(),
+                index: 0,
+                container_name: "abc-abc-0".to_string(),
+            }],
+        }
+    }
+
+    fn frame(tick: u32) -> Frame {
+        Frame {
+            tick,
+            tanks: vec![TankFrame {
+                index: 0,
+                commands: Commands::MOVE_FORWARD | Commands::FIRE,
+                x: 1.5,
+                y: -2.5,
+                rotation: 0.25,
+                gun_rotation: 0.5,
+                radar_rotation: 0.75,
+                health: 90,
+                radar_disabled: true,
+            }],
+            bullets: vec![BulletFrame {
+                id: 3,
+                tank: 0,
+                x: 10.,
+                y: 20.,
+                vx: 200.,
+                vy: 0.,
+                bounces: 2,
+            }],
+            events: vec![EventFrame {
+                tank: 0,
+                event_type: "radar_scan".to_string(),
+                info: json!({ "collision_type": "Tank" }),
+            }],
+            obstacles: vec![
+                ObstacleFrame {
+                    index: 0,
+                    x: 5.,
+                    y: 6.,
+                    health: Some(40),
+                },
+                ObstacleFrame {
+                    index: 1,
+                    x: 7.,
+                    y: 8.,
+                    health: None,
+                },
+            ],
+        }
+    }
+
+    /// Writes a replay the way `ReplayWriter` did at `version`.
+    fn write_versioned(version: u16, frames: &[Frame], results: &Value) -> Vec<u8> {
+        let mut w = vec![];
+        w.extend_from_slice(REPLAY_MAGIC);
+        write_u16(&mut w, version).unwrap();
+        write_json(&mut w, &serde_json::to_value(header()).unwrap()).unwrap();
+
+        for frame in frames {
+            write_u8(&mut w, FRAME_TAG).unwrap();
+            write_u32(&mut w, frame.tick).unwrap();
+            write_u16(&mut w, frame.tanks.len() as u16).unwrap();
+            for tank in &frame.tanks {
+                write_u16(&mut w, tank.index as u16).unwrap();
+                write_u32(&mut w, tank.commands).unwrap();
+                for v in [
+                    tank.x,
+                    tank.y,
+                    tank.rotation,
+                    tank.gun_rotation,
+                    tank.radar_rotation,
+                ] {
+                    write_f32(&mut w, v).unwrap();
+                }
+                write_i32(&mut w, tank.health).unwrap();
+                write_u8(&mut w, tank.radar_disabled as u8).unwrap();
+            }
+            write_u16(&mut w, frame.bullets.len() as u16).unwrap();
+            for bullet in &frame.bullets {
+                write_u32(&mut w, bullet.id).unwrap();
+                write_u16(&mut w, bullet.tank as u16).unwrap();
+                for v in [bullet.x, bullet.y, bullet.vx, bullet.vy] {
+                    write_f32(&mut w, v).unwrap();
+                }
+                if version >= 3 {
+                    write_u8(&mut w, bullet.bounces as u8).unwrap();
+                }
+            }
+            write_u16(&mut w, frame.events.len() as u16).unwrap();
+            for event in &frame.events {
+                write_u16(&mut w, event.tank as u16).unwrap();
+                write_str(&mut w, &event.event_type).unwrap();
+                write_json(&mut w, &event.info).unwrap();
+            }
+            if version >= 2 {
+                write_u16(&mut w, frame.obstacles.len() as u16).unwrap();
+                for obstacle in &frame.obstacles {
+                    write_u16(&mut w, obstacle.index as u16).unwrap();
+                    write_f32(&mut w, obstacle.x).unwrap();
+                    write_f32(&mut w, obstacle.y).unwrap();
+                    write_i32(&mut w, obstacle.health.unwrap_or(-1)).unwrap();
+                }
+            }
+        }
+
+        write_u8(&mut w, RESULTS_TAG).unwrap();
+        write_json(&mut w, results).unwrap();
+        w
+    }
+
+    /// What the current reader makes of `frame` stored at `version`.
+    fn as_read_at(version: u16, frame: &Frame) -> Frame {
+        let mut frame = frame.clone();
+        if version < 2 {
+            frame.obstacles.clear();
+        }
+        if version < 3 {
+            for bullet in &mut frame.bullets {
+                bullet.bounces = 0;
+            }
+        }
+        frame
+    }
+
+    #[test]
+    fn round_trip() {
+        let frames = vec![frame(0), frame(1)];
+        let results = json!({ "winner": "abc-abc-0" });
+
+        let mut writer = ReplayWriter::new(vec![], &header()).unwrap();
+        for frame in &frames {
+            writer.write_frame(frame).unwrap();
+        }
+        let bytes = writer.finish(&results).unwrap();
+
+        let reader = ReplayReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.version, REPLAY_VERSION);
+        let (read_header, read_frames, read_results) = reader.read_all().unwrap();
+        assert_eq!(read_header.rules, header().rules);
+        assert_eq!(read_header.map, header().map);
+        assert_eq!(read_header.tanks[0].container_name, "abc-abc-0");
+        assert_eq!(read_frames, frames);
+        assert_eq!(read_results, Some(results));
+    }
+
+    #[test]
+    fn writer_matches_the_current_version_layout() {
+        let frames = vec![frame(0)];
+        let results = json!(null);
+
+        let mut writer = ReplayWriter::new(vec![], &header()).unwrap();
+        writer.write_frame(&frames[0]).unwrap();
+
+        assert_eq!(
+            writer.finish(&results).unwrap(),
+            write_versioned(REPLAY_VERSION, &frames, &results)
+        );
+    }
+
+    #[test]
+    fn reads_every_version() {
+        let frames = vec![frame(0), frame(1)];
+        let results = json!({ "winner": "" });
+
+        for version in 1..=REPLAY_VERSION {
+            let bytes = write_versioned(version, &frames, &results);
+            let reader = ReplayReader::new(bytes.as_slice()).unwrap();
+            assert_eq!(reader.version, version);
+
+            let (_, read_frames, read_results) = reader.read_all().unwrap();
+            let expected = frames
+                .iter()
+                .map(|f| as_read_at(version, f))
+                .collect::<Vec<Frame>>();
+            assert_eq!(read_frames, expected, "version {}", version);
+            assert_eq!(read_results, Some(results.clone()), "version {}", version);
+        }
+    }
+
+    #[test]
+    fn reads_v1_without_obstacles_or_bounces() {
+        let bytes = write_versioned(1, &[frame(5)], &json!(null));
+        let (_, frames, _) = ReplayReader::new(bytes.as_slice())
+            .unwrap()
+            .read_all()
+            .unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].tick, 5);
+        assert_eq!(frames[0].tanks, frame(5).tanks);
+        assert!(frames[0].obstacles.is_empty());
+        assert_eq!(frames[0].bullets[0].bounces, 0);
+        assert_eq!(frames[0].events, frame(5).events);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let bytes = write_versioned(REPLAY_VERSION + 1, &[], &json!(null));
+        assert!(ReplayReader::new(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn truncated_replays_end_without_results() {
+        let mut bytes = write_versioned(REPLAY_VERSION, &[frame(0)], &json!(null));
+        // drop the results record
+        bytes.truncate(bytes.len() - 1 - 4 - "null".len());
+
+        let (_, frames, results) = ReplayReader::new(bytes.as_slice())
+            .unwrap()
+            .read_all()
+            .unwrap();
+        assert_eq!(frames, vec![frame(0)]);
+        assert_eq!(results, None);
+    }
+}
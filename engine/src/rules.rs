@@ -0,0 +1,287 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{c_wreck::WreckMode, game_mode::GameModeKind};
+
+/// Tunable match rules. `CoreCTPlugin` inserts the defaults unless the app already has rules,
+/// and recordings store them in the replay header.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// decides the win condition and scoring, see `GameMode`
+    pub mode: GameModeKind,
+    /// tanks within this distance of a shot get a `gunshot_heard` event
+    pub gunshot_hearing_radius: f32,
+    /// the direction in `gunshot_heard` is rounded to one of this many sectors
+    pub gunshot_direction_sectors: u32,
+    /// tanks are split round robin by index into this many teams, 0 for free-for-all
+    pub teams: u32,
+    /// ticks a tank has to wait between two TEAM_MESSAGEs
+    pub team_message_cooldown: u32,
+    /// seeds everything random in a match, such as pickups
+    pub seed: u64,
+    /// ticks between pickup spawns, 0 to turn pickups off
+    pub pickup_interval: u32,
+    pub max_pickups: u32,
+    pub pickup_repair: i32,
+    /// ticks rapid fire and shields last
+    pub pickup_duration: u32,
+    /// battle royale zone that shrinks and damages tanks outside of it
+    pub safe_zone: bool,
+    pub safe_zone_start_tick: u32,
+    /// ticks the zone takes to shrink down to `safe_zone_min_radius`
+    pub safe_zone_shrink_ticks: u32,
+    pub safe_zone_min_radius: f32,
+    pub safe_zone_damage: i32,
+    /// ticks between damage to tanks outside the zone
+    pub safe_zone_damage_interval: u32,
+    /// king of the hill
+    pub hill_radius: f32,
+    /// ticks a tank has to hold the hill to win
+    pub hill_target_ticks: u32,
+    /// capture the flag: captures a team needs to win
+    pub flag_captures: u32,
+    /// respawn deathmatch: ticks before a destroyed tank comes back
+    pub respawn_delay: u32,
+    /// ticks a respawned tank is shielded for
+    pub respawn_invulnerability: u32,
+    /// what destroyed tanks leave behind
+    pub wreck: WreckMode,
+    /// scale wall and tank collision damage with the impact speed instead of `Tank::COLLISION_DAMAGE`
+    pub velocity_collision_damage: bool,
+    /// damage per unit of impact speed, a head-on wall hit at full speed is 100
+    pub collision_damage_per_speed: f32,
+    pub max_collision_damage: i32,
+    /// ticks before a bullet that hit nothing expires, 0 to keep it until it hits something
+    pub bullet_lifetime: u32,
+    /// distance from the gun after which a bullet expires, 0 for no limit
+    pub bullet_max_range: f32,
+    /// times a bullet bounces off walls and indestructible obstacles before it is gone, 0 for none
+    pub ricochet_bounces: u32,
+    /// bullet damage is multiplied by this for every bounce
+    pub ricochet_damage_factor: f32,
+    /// no boundary walls, tanks and bullets leaving one edge come back on the opposite one
+    pub wrap_around: bool,
+    /// standard shots worth of heat the gun holds, it fires while there is room for one more
+    pub gun_heat_capacity: u32,
+    /// heat the gun loses every tick
+    pub gun_heat_dissipation: u32,
+}
+
+impl Rules {
+    /// Teams the tanks are split into, 0 for free-for-all. Capture the flag always has at
+    /// least two sides.
+    pub fn n_teams(&self) -> usize {
+        match self.mode {
+            GameModeKind::CaptureTheFlag => (self.teams as usize).max(2),
+            _ => self.teams as usize,
+        }
+    }
+
+    pub fn team_of(&self, index: usize) -> Option<usize> {
+        match self.n_teams() {
+            0 => None,
+            n_teams => Some(index % n_teams),
+        }
+    }
+
+    /// Applies comma separated `name=value` options such as `teams=2`. This is how rules, and
+    /// the map, are given on the command line, to `/run/{mode}/{options}` and in sim jobs.
+    pub fn with_options(mut self, options: &str) -> Result<Rules, String> {
+        for option in options.split(',').filter(|f| !f.is_empty()) {
+            let (name, value) = option.split_once('=').unwrap_or((option, ""));
+            match name {
+                "teams" => self.teams = parse_option(name, value)?,
+                "seed" => self.seed = parse_option(name, value)?,
+                "pickup_interval" => self.pickup_interval = parse_option(name, value)?,
+                "safe_zone" => self.safe_zone = parse_option(name, value)?,
+                "wrap_around" => self.wrap_around = parse_option(name, value)?,
+                "wreck" => self.wreck = parse_option(name, value)?,
+                "velocity_collision_damage" => {
+                    self.velocity_collision_damage = parse_option(name, value)?
+                }
+                "max_collision_damage" => self.max_collision_damage = parse_option(name, value)?,
+                "ricochet_bounces" => self.ricochet_bounces = parse_option(name, value)?,
+                "ricochet_damage_factor" => {
+                    self.ricochet_damage_factor = parse_option(name, value)?
+                }
+                // picked up by `Map::from_options`
+                "map" => {}
+                _ => return Err(format!("unknown rule option '{}'", name)),
+            }
+        }
+        Ok(self)
+    }
+
+    /// Rules stored in a replay header. Missing fields, as in older recordings, use the defaults.
+    pub fn from_header(rules: &serde_json::Value) -> Result<Rules, serde_json::Error> {
+        if rules.is_null() {
+            return Ok(Rules::default());
+        }
+        serde_json::from_value(rules.clone())
+    }
+}
+
+fn parse_option<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for rule option '{}'", value, name))
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            mode: GameModeKind::Deathmatch,
+            gunshot_hearing_radius: 400.0,
+            gunshot_direction_sectors: 8,
+            teams: 0,
+            team_message_cooldown: 10,
+            seed: 0,
+            pickup_interval: 0,
+            max_pickups: 3,
+            pickup_repair: 30,
+            pickup_duration: 300,
+            safe_zone: false,
+            safe_zone_start_tick: 120,
+            safe_zone_shrink_ticks: 360,
+            safe_zone_min_radius: 100.0,
+            safe_zone_damage: 5,
+            safe_zone_damage_interval: 30,
+            hill_radius: 100.0,
+            hill_target_ticks: 300,
+            flag_captures: 3,
+            respawn_delay: 120,
+            respawn_invulnerability: 90,
+            wreck: WreckMode::Remain,
+            velocity_collision_damage: false,
+            collision_damage_per_speed: 0.1,
+            max_collision_damage: 30,
+            bullet_lifetime: 600,
+            bullet_max_range: 0.0,
+            ricochet_bounces: 0,
+            ricochet_damage_factor: 0.5,
+            wrap_around: false,
+            gun_heat_capacity: 1,
+            gun_heat_dissipation: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_override_the_defaults() {
+        let rules = Rules::default().with_options("teams=2").unwrap();
+        assert_eq!(rules.teams, 2);
+        assert_eq!(
+            rules.team_message_cooldown,
+            Rules::default().team_message_cooldown
+        );
+    }
+
+    #[test]
+    fn empty_options_change_nothing() {
+        let rules = Rules::default().with_options("").unwrap();
+        assert_eq!(rules.teams, 0);
+        assert!(Rules::default().with_options(",").is_ok());
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        assert!(Rules::default().with_options("players=2").is_err());
+        assert!(Rules::default().with_options("teams=two").is_err());
+        assert!(Rules::default().with_options("teams").is_err());
+        assert!(Rules::default().with_options("teams=-1").is_err());
+    }
+
+    #[test]
+    fn team_of_splits_round_robin() {
+        let rules = Rules {
+            teams: 3,
+            ..default()
+        };
+        assert_eq!(rules.team_of(0), Some(0));
+        assert_eq!(rules.team_of(2), Some(2));
+        assert_eq!(rules.team_of(4), Some(1));
+        assert_eq!(Rules::default().team_of(4), None);
+    }
+
+    #[test]
+    fn wrap_around_is_an_option() {
+        assert!(
+            Rules::default()
+                .with_options("wrap_around=true")
+                .unwrap()
+                .wrap_around
+        );
+        assert!(
+            !Rules::default()
+                .with_options("wrap_around=false")
+                .unwrap()
+                .wrap_around
+        );
+        assert!(Rules::default().with_options("wrap_around=yes").is_err());
+    }
+
+    #[test]
+    fn ricochets_are_options() {
+        let rules = Rules::default()
+            .with_options("ricochet_bounces=3,ricochet_damage_factor=0.75")
+            .unwrap();
+        assert_eq!(rules.ricochet_bounces, 3);
+        assert_eq!(rules.ricochet_damage_factor, 0.75);
+    }
+
+    #[test]
+    fn wreck_is_an_option() {
+        for wreck in WreckMode::ALL {
+            let rules = Rules::default()
+                .with_options(&format!("wreck={}", wreck.name()))
+                .unwrap();
+            assert_eq!(rules.wreck, wreck);
+        }
+        assert!(Rules::default().with_options("wreck=burning").is_err());
+    }
+
+    #[test]
+    fn velocity_collision_damage_is_an_option() {
+        assert!(!Rules::default().velocity_collision_damage);
+        let rules = Rules::default()
+            .with_options("velocity_collision_damage=true,max_collision_damage=20")
+            .unwrap();
+        assert!(rules.velocity_collision_damage);
+        assert_eq!(rules.max_collision_damage, 20);
+    }
+
+    #[test]
+    fn header_rules_fill_in_defaults() {
+        let rules = Rules::from_header(&serde_json::json!({ "teams": 2 })).unwrap();
+        assert_eq!(rules.teams, 2);
+        assert_eq!(rules.pickup_interval, Rules::default().pickup_interval);
+        assert!(Rules::from_header(&serde_json::json!({ "legacy": true })).is_ok());
+        assert!(Rules::from_header(&serde_json::Value::Null).is_ok());
+        assert!(Rules::from_header(&serde_json::json!({ "teams": "two" })).is_err());
+    }
+
+    #[test]
+    fn seed_is_an_option() {
+        let rules = Rules::default().with_options("seed=42").unwrap();
+        assert_eq!(rules.seed, 42);
+        assert!(Rules::default().with_options("seed=-1").is_err());
+    }
+
+    #[test]
+    fn capture_the_flag_has_two_sides_at_least() {
+        let rules = Rules {
+            mode: GameModeKind::CaptureTheFlag,
+            ..default()
+        };
+        assert_eq!(rules.n_teams(), 2);
+        assert_eq!(rules.team_of(3), Some(1));
+        assert_eq!(Rules { teams: 3, ..rules }.team_of(3), Some(0));
+    }
+}
@@ -1,18 +1,25 @@
 use bevy::prelude::{
-    default, Commands as BevyCommands, Entity, Query, ResMut, SpatialBundle, Transform, Vec2, Vec3,
-    Visibility, Without,
+    default, Commands as BevyCommands, Entity, EventWriter, Quat, Query, Res, ResMut,
+    SpatialBundle, Transform, Vec2, Vec3, Visibility, Without,
 };
 // use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use ct_api::Commands;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::f32::consts::PI;
 
 use crate::{
+    c_chassis::Chassis,
     c_command_source::CommandSource,
-    c_event::{CTEvent, EventSink},
+    c_event::{CTEvent, EventSink, TeamMessage, TeamRadio},
     c_health::Health,
-    c_tank::{Bullet, Gun, Radar, Tank},
+    c_pickup::PowerUps,
+    c_tank::{Bullet, Gun, Radar, Tank, TankId},
+    observer::{ShotFired, TankDestroyed},
+    rules::Rules,
+    s_safe_zone::SafeZone,
     c_radar_needs_update::RadarNeedsUpdate,
+    map::{Map, TerrainKind},
     CCollider, CollisionMask, CollisionType, TickState,
 };
 
@@ -21,12 +28,15 @@ pub fn apply_commands(
     mut query: Query<
         (
             Entity,
+            &TankId,
             &mut CommandSource,
             &Transform,
             &mut Velocity,
             &mut Tank,
             &mut Health,
             &mut EventSink,
+            &mut PowerUps,
+            &Chassis,
         ),
         (Without<Radar>, Without<Gun>),
     >,
@@ -39,21 +49,38 @@ pub fn apply_commands(
         (Without<Radar>, Without<Tank>),
     >,
     mut state: ResMut<TickState>,
+    mut shots_fired: EventWriter<ShotFired>,
+    mut tanks_destroyed: EventWriter<TankDestroyed>,
+    rapier_context: Res<RapierContext>,
+    query_collider: Query<&CCollider>,
+    rules: Res<Rules>,
+    mut team_radio: ResMut<TeamRadio>,
+    safe_zone: Res<SafeZone>,
+    map: Res<Map>,
 ) {
     state.count += 1;
 
+    let team_messages = std::mem::take(&mut team_radio.pending);
+
+    // (shooter, position) of every shot this tick, heard by the other tanks afterwards
+    let mut gunshots: Vec<(Entity, Vec2)> = vec![];
+
     for (
         entity,
+        tank_id,
         mut command_receiver,
         transform,
         mut velocity,
         mut tank,
         mut health,
         mut event_sink,
+        mut power_ups,
+        chassis,
     ) in &mut query
     {
         let mut vel = Vec2::ZERO;
         let mut ang = 0.0;
+        tank.last_velocity = velocity.linvel;
         velocity.linvel = vel;
         velocity.angvel = ang;
 
@@ -77,19 +104,24 @@ pub fn apply_commands(
         let grouped_commands = command_receiver.queue.remove(0);
         if Commands::SELF_DESTRUCT & grouped_commands != 0 {
             health.val = 0;
+            tanks_destroyed.send(TankDestroyed {
+                tank: entity,
+                tank_id: *tank_id,
+                by: None,
+            });
             continue;
         }
 
         if Commands::MOVE_FORWARD & grouped_commands != 0 {
             let dir = transform.rotation * Vec3::Y;
 
-            vel.x += Tank::MOVEMENT_SPEED * dir.x;
-            vel.y += Tank::MOVEMENT_SPEED * dir.y;
+            vel.x += chassis.movement_speed() * dir.x;
+            vel.y += chassis.movement_speed() * dir.y;
         }
         if Commands::MOVE_BACKWARD & grouped_commands != 0 {
             let dir = transform.rotation * Vec3::Y;
-            vel.x -= Tank::MOVEMENT_SPEED * dir.x;
-            vel.y -= Tank::MOVEMENT_SPEED * dir.y;
+            vel.x -= chassis.movement_speed() * dir.x;
+            vel.y -= chassis.movement_speed() * dir.y;
         }
         if Commands::LOCK_GUN & grouped_commands != 0 {
             gun.locked = true;
@@ -104,24 +136,24 @@ pub fn apply_commands(
             radar.locked = false;
         }
         if Commands::ROTATE_TANK_CLOCKWISE & grouped_commands != 0 {
-            ang -= Tank::ROTATION_SPEED;
+            ang -= chassis.rotation_speed();
 
             if gun.locked {
-                gun_ang -= Tank::ROTATION_SPEED;
+                gun_ang -= chassis.rotation_speed();
 
                 if radar.locked {
-                    radar_ang -= Tank::ROTATION_SPEED;
+                    radar_ang -= chassis.rotation_speed();
                 }
             }
         }
         if Commands::ROTATE_TANK_COUNTER_CLOCKWISE & grouped_commands != 0 {
-            ang += Tank::ROTATION_SPEED;
+            ang += chassis.rotation_speed();
 
             if gun.locked {
-                gun_ang += Tank::ROTATION_SPEED;
+                gun_ang += chassis.rotation_speed();
 
                 if radar.locked {
-                    radar_ang += Tank::ROTATION_SPEED;
+                    radar_ang += chassis.rotation_speed();
                 }
             }
         }
@@ -145,15 +177,49 @@ pub fn apply_commands(
         if Commands::ROTATE_RADAR_COUNTER_CLOCKWISE & grouped_commands != 0 {
             radar_ang += Tank::ROTATION_SPEED;
         }
-        if Commands::FIRE & grouped_commands != 0 && tank.cooldown == 0 {
+        // the gun fires while it has room for a standard shot, more powerful ones can overheat it
+        let max_heat = chassis.shot_heat() * rules.gun_heat_capacity;
+        let overheated = tank.heat + chassis.shot_heat() > max_heat;
+        // bots tend to send FIRE every tick, so only the first rejection until the next shot is reported
+        if Commands::FIRE & grouped_commands != 0 && overheated && !tank.rejected_since_last_shot {
+            tank.rejected_since_last_shot = true;
+            event_sink.queue.push(CTEvent {
+                event_type: "command_rejected".to_string(),
+                info: json!({
+                    "command": Commands::FIRE,
+                    "reason": "overheated",
+                    "heat": tank.heat,
+                    "max_heat": max_heat,
+                }),
+            });
+        }
+        if Commands::FIRE & grouped_commands != 0 && !overheated {
+            tank.rejected_since_last_shot = false;
+            let power = Commands::fire_power(grouped_commands);
             let t = gun_transform.rotation * Vec3::Y;
-            commands.spawn((
+            let bullet_translation = transform.translation
+                + t * Vec3::new(
+                    chassis.radius() + Bullet::RADIUS * 2.,
+                    chassis.radius() + Bullet::RADIUS * 2.,
+                    1.0,
+                );
+            let bullet_velocity = Vec2::new(t.x * Bullet::SPEED, t.y * Bullet::SPEED);
+            let bullet = commands.spawn((
                 CCollider {
                     collision_type: CollisionType::Bullet,
                 },
                 ActiveEvents::COLLISION_EVENTS,
                 Sensor,
-                Bullet { tank: entity },
+                Bullet {
+                    tank: entity,
+                    last_position: bullet_translation.truncate(),
+                    distance: 0.,
+                    fired_at: state.count,
+                    bounces: 0,
+                    power,
+                },
+                // fast bullets would otherwise pass through thin walls between two steps
+                Ccd::enabled(),
                 GravityScale(0.0),
                 RigidBody::Dynamic,
                 // ColliderMassProperties::Mass(1.0),
@@ -174,27 +240,35 @@ pub fn apply_commands(
                     angular_damping: 0.0,
                 },
                 Velocity {
-                    linvel: Vec2::new(t.x * Bullet::SPEED, t.y * Bullet::SPEED),
+                    linvel: bullet_velocity,
                     angvel: 0.0,
                 },
                 SpatialBundle {
                     transform: {
-                        let mut t = Transform::from_translation(
-                            transform.translation
-                                + t * Vec3::new(
-                                    Tank::RADIUS + Bullet::RADIUS * 2.,
-                                    Tank::RADIUS + Bullet::RADIUS * 2.,
-                                    1.0,
-                                ),
-                        );
+                        let mut t = Transform::from_translation(bullet_translation);
                         t.translation.z = 2.5;
                         t
                     },
                     visibility: Visibility::Visible,
                     ..default()
                 },
-            ));
-            tank.cooldown = Tank::MAX_COOLDOWN;
+            )).id();
+            tank.heat += if power_ups.rapid_fire > 0 {
+                chassis.shot_heat() * power / PowerUps::RAPID_FIRE_HEAT_DIVISOR
+            } else {
+                chassis.shot_heat() * power
+            };
+
+            shots_fired.send(ShotFired {
+                tank: entity,
+                tank_id: *tank_id,
+                bullet,
+                x: bullet_translation.x,
+                y: bullet_translation.y,
+                vx: bullet_velocity.x,
+                vy: bullet_velocity.y,
+            });
+            gunshots.push((entity, transform.translation.truncate()));
         }
 
         if Commands::REQUEST_INFO & grouped_commands != 0 {
@@ -213,15 +287,66 @@ pub fn apply_commands(
                     "gun": {
 
                             "rotation": v2.y.atan2(v2.x),
+                            "heat": tank.heat,
+                            "max_heat": chassis.shot_heat() * rules.gun_heat_capacity,
                     },
                     "radar": {
 
                             "rotation": v3.y.atan2(v3.x),
-                    }
+                    },
+                    "power_ups": {
+                        "rapid_fire": power_ups.rapid_fire,
+                        "shield": power_ups.shield,
+                    },
+                    "chassis": chassis.to_json(),
+                    "safe_zone": safe_zone.to_json(),
+                    "terrain": map.terrain_at(transform.translation.truncate()),
+                }),
+            });
+        }
+
+        if Commands::SCAN_WALLS & grouped_commands != 0 {
+            event_sink.queue.push(CTEvent {
+                event_type: "scan_walls".to_string(),
+                info: json!({
+                    "rays": scan_walls(entity, transform, &rapier_context, &query_collider),
                 }),
             });
         }
 
+        if Commands::TEAM_MESSAGE & grouped_commands != 0 {
+            let ready_at = team_radio
+                .last_sent
+                .get(&tank_id.0)
+                .map_or(0, |last_sent| last_sent + rules.team_message_cooldown);
+            match rules.team_of(tank_id.0) {
+                Some(team) if state.count >= ready_at => {
+                    team_radio.last_sent.insert(tank_id.0, state.count);
+                    team_radio.pending.push(TeamMessage {
+                        from: tank_id.0,
+                        team,
+                        payload: Commands::team_message_payload(grouped_commands),
+                        tick: state.count,
+                    });
+                }
+                Some(_) => event_sink.queue.push(CTEvent {
+                    event_type: "command_rejected".to_string(),
+                    info: json!({
+                        "command": Commands::TEAM_MESSAGE,
+                        "reason": "cooldown",
+                        "ready_at": ready_at,
+                    }),
+                }),
+                None => event_sink.queue.push(CTEvent {
+                    event_type: "command_rejected".to_string(),
+                    info: json!({
+                        "command": Commands::TEAM_MESSAGE,
+                        "reason": "no_team",
+                    }),
+                }),
+            }
+        }
+
         if Commands::DISABLE_RADAR & grouped_commands != 0 {
             radar.disabled = true;
             commands.entity(tank.radar).insert(RadarNeedsUpdate);
@@ -236,8 +361,12 @@ pub fn apply_commands(
             command_receiver.queue.clear();
         }
 
-        if tank.cooldown > 0 {
-            tank.cooldown -= 1;
+        tank.heat = tank.heat.saturating_sub(rules.gun_heat_dissipation);
+        power_ups.rapid_fire = power_ups.rapid_fire.saturating_sub(1);
+        power_ups.shield = power_ups.shield.saturating_sub(1);
+
+        if map.in_terrain(transform.translation.truncate(), TerrainKind::Mud) {
+            vel *= TerrainKind::MUD_SPEED_MULTIPLIER;
         }
 
         velocity.linvel = vel;
@@ -253,4 +382,97 @@ pub fn apply_commands(
         // let _v2 = radar_transform.rotation * Vec3::Y;
         // println!("angle: {} {} {}", _v.y.atan2(_v.x), _v2.y.atan2(_v.x), _v2.y.atan2(_v.x));
     }
+
+    if gunshots.is_empty() && team_messages.is_empty() {
+        return;
+    }
+
+    for (entity, tank_id, _, transform, _, _, health, mut event_sink, _) in &mut query {
+        if health.val == 0 {
+            continue;
+        }
+
+        for message in &team_messages {
+            if message.from == tank_id.0 || rules.team_of(tank_id.0) != Some(message.team) {
+                continue;
+            }
+
+            event_sink.queue.push(CTEvent {
+                event_type: "team_message".to_string(),
+                info: json!({
+                    "from": message.from,
+                    "payload": message.payload,
+                    "tick": message.tick,
+                }),
+            });
+        }
+
+        let position = transform.translation.truncate();
+
+        for (shooter, shot_position) in &gunshots {
+            if *shooter == entity || position.distance(*shot_position) > rules.gunshot_hearing_radius {
+                continue;
+            }
+
+            // only the rough direction to the shooter is given away, not the position
+            let offset = *shot_position - position;
+            let sector = 2. * PI / rules.gunshot_direction_sectors.max(1) as f32;
+            let rotation = (offset.y.atan2(offset.x) / sector).round() * sector;
+
+            event_sink.queue.push(CTEvent {
+                event_type: "gunshot_heard".to_string(),
+                info: json!({
+                    "rotation": rotation,
+                }),
+            });
+        }
+    }
+}
+
+/// Distance from the tank's centre to the nearest wall or tank along each of
+/// `Tank::SCAN_WALLS_RAYS` directions, using the same angle convention as `request_info`.
+fn scan_walls(
+    entity: Entity,
+    transform: &Transform,
+    rapier_context: &RapierContext,
+    query_collider: &Query<&CCollider>,
+) -> Vec<Value> {
+    let filter = QueryFilter::new()
+        .exclude_sensors()
+        .exclude_collider(entity)
+        .groups(CollisionGroups::new(
+            Group::from_bits_truncate(CollisionMask::TANK),
+            Group::from_bits_truncate(CollisionMask::WALL | CollisionMask::TANK),
+        ));
+
+    (0..Tank::SCAN_WALLS_RAYS)
+        .map(|i| {
+            let offset = 2. * PI * i as f32 / Tank::SCAN_WALLS_RAYS as f32;
+            let dir = (transform.rotation * Quat::from_rotation_z(offset)) * Vec3::Y;
+
+            let hit = rapier_context.cast_ray(
+                transform.translation.truncate(),
+                dir.truncate(),
+                Tank::SCAN_WALLS_MAX_DISTANCE,
+                true,
+                filter,
+            );
+
+            match hit {
+                Some((hit_entity, distance)) => json!({
+                    "rotation": dir.y.atan2(dir.x),
+                    "distance": distance,
+                    "collision_type": query_collider
+                        .get(hit_entity)
+                        .map(|f| format!("{:?}", f.collision_type))
+                        .ok(),
+                }),
+                None => json!({
+                    "rotation": dir.y.atan2(dir.x),
+                    "distance": Value::Null,
+                    "collision_type": Value::Null,
+                }),
+            }
+        })
+        .collect()
 }
@@ -1,35 +1,228 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 use bevy_rapier2d::prelude::*;
+use serde_json::json;
 
 use crate::{
-    c_event::{generate_event, EventSink},
-    c_tank::{Bullet, Radar},
-    CCollider,
+    c_event::{CTEvent, generate_event, EventSink},
+    c_obstacle::Obstacle,
+    c_tank::{Bullet, Radar, TankId},
+    observer::ObstacleDestroyed,
+    rules::Rules,
+    s_wrap_arena::wrapped_delta,
+    CCollider, CollisionType, Game, TickState,
 };
 
 pub fn bullet_physics(
-    rapier_context: Res<RapierContext>,
+    mut contact_events: EventReader<CollisionEvent>,
     mut commands: Commands,
     mut query_event_sink: Query<&mut EventSink>,
-    query_bullet: Query<(Entity, &Bullet)>,
-    query_collidable: Query<(Entity, &CCollider, &Transform, Option<&Velocity>), Without<Radar>>,
+    mut query_bullet: Query<(Entity, &mut Bullet, &Transform)>,
+    query_collidable: Query<(&CCollider, &Transform, Option<&Velocity>), Without<Radar>>,
+    mut query_obstacle: Query<&mut Obstacle>,
+    query_shape: Query<&Collider>,
+    query_tank_id: Query<&TankId>,
+    mut obstacles_destroyed: EventWriter<ObstacleDestroyed>,
+    state: Res<TickState>,
+    rules: Res<Rules>,
 ) {
-    for (collision_entity, ccollider, collision_entity_transform, collision_entity_velocity) in
-        query_collidable.iter()
-    {
-        for (bullet_entity, bullet) in query_bullet.iter() {
-            /* Find the intersection pair, if it exists, between two colliders. */
-            if rapier_context.intersection_pair(collision_entity, bullet_entity) == Some(true) {
-                generate_event(
+    let mut despawned = HashSet::new();
+
+    for contact_event in contact_events.iter() {
+        let CollisionEvent::Started(collision_entity_1, collision_entity_2, _event_flag) =
+            contact_event
+        else {
+            continue;
+        };
+
+        for (bullet_entity, collision_entity) in [
+            (collision_entity_1, collision_entity_2),
+            (collision_entity_2, collision_entity_1),
+        ] {
+            if despawned.contains(bullet_entity) {
+                continue;
+            }
+            let Ok((_, mut bullet, bullet_transform)) = query_bullet.get_mut(*bullet_entity) else {
+                continue;
+            };
+            let Ok((ccollider, collision_entity_transform, collision_entity_velocity)) =
+                query_collidable.get(*collision_entity)
+            else {
+                continue;
+            };
+
+            // with ricochets on, walls and indestructible obstacles bounce bullets back
+            let bounces = bullet.bounces < rules.ricochet_bounces
+                && match ccollider.collision_type {
+                    CollisionType::Wall => true,
+                    CollisionType::Obstacle => query_obstacle
+                        .get(*collision_entity)
+                        .map_or(true, |f| f.health.is_none()),
+                    _ => false,
+                };
+            if bounces {
+                let center = collision_entity_transform.translation.truncate();
+                let position = bullet_transform.translation.truncate();
+                let normal = match query_shape.get(*collision_entity).ok().and_then(|f| f.as_cuboid()) {
+                    Some(cuboid) => box_normal(center, cuboid.half_extents(), position),
+                    // round static wrecks
+                    None => (position - center).normalize_or_zero(),
+                };
+                let velocity = query_collidable
+                    .get(*bullet_entity)
+                    .ok()
+                    .and_then(|f| f.2.copied())
+                    .unwrap_or_default();
+                let linvel = if velocity.linvel.dot(normal) < 0. {
+                    velocity.linvel - 2. * velocity.linvel.dot(normal) * normal
+                } else {
+                    velocity.linvel
+                };
+
+                bullet.bounces += 1;
+                commands.entity(*bullet_entity).insert(Velocity::linear(linvel));
+
+                if let Ok(mut event_sink) = query_event_sink.get_mut(bullet.tank) {
+                    let mut event = generate_event(
+                        "bullet_hit".to_string(),
+                        collision_entity,
+                        collision_entity_transform,
+                        collision_entity_velocity,
+                        &ccollider.collision_type,
+                    );
+                    event.info["bounced"] = true.into();
+                    event.info["bounces"] = bullet.bounces.into();
+                    event_sink.queue.push(event);
+                }
+                continue;
+            }
+
+            // bullets wear down destructible obstacles
+            let mut obstacle_info = None;
+            if let Ok(mut obstacle) = query_obstacle.get_mut(*collision_entity) {
+                if let Some(health) = obstacle.health.as_mut() {
+                    *health = (*health - Obstacle::BULLET_DAMAGE).max(0);
+                    if *health == 0 && !despawned.contains(collision_entity) {
+                        commands.entity(*collision_entity).despawn_recursive();
+                        despawned.insert(*collision_entity);
+                        obstacles_destroyed.send(ObstacleDestroyed {
+                            obstacle: *collision_entity,
+                            index: obstacle.index,
+                            by: query_tank_id.get(bullet.tank).ok().copied(),
+                        });
+                    }
+                }
+                obstacle_info = Some(obstacle.to_json());
+            }
+
+            if let Ok(mut event_sink) = query_event_sink.get_mut(bullet.tank) {
+                let mut event = generate_event(
                     "bullet_hit".to_string(),
-                    &mut query_event_sink.get_mut(bullet.tank).unwrap(),
-                    &collision_entity,
+                    collision_entity,
                     collision_entity_transform,
                     collision_entity_velocity,
                     &ccollider.collision_type,
                 );
-                commands.entity(bullet_entity).despawn_recursive();
+                if let Some(obstacle_info) = obstacle_info {
+                    event.info["obstacle"] = obstacle_info;
+                }
+                event_sink.queue.push(event);
             }
+            commands.entity(*bullet_entity).despawn_recursive();
+            despawned.insert(*bullet_entity);
         }
     }
+
+    // bullets that hit nothing
+    for (bullet_entity, mut bullet, transform) in &mut query_bullet {
+        if despawned.contains(&bullet_entity) {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let ticks = state.count.saturating_sub(bullet.fired_at);
+        // measured tick by tick, so bounces add up and wrapping around doesn't
+        bullet.distance += if rules.wrap_around {
+            wrapped_delta(bullet.last_position, position).length()
+        } else {
+            position.distance(bullet.last_position)
+        };
+        bullet.last_position = position;
+        let distance = bullet.distance;
+        // past the outer edge of the walls
+        let margin = Game::WALL_THICKNESS * 2.;
+        let reason = if position.x.abs() > Game::WIDTH / 2. + margin
+            || position.y.abs() > Game::HEIGHT / 2. + margin
+        {
+            "out_of_bounds"
+        } else if rules.bullet_lifetime > 0 && ticks >= rules.bullet_lifetime {
+            "expired"
+        } else if rules.bullet_max_range > 0. && distance >= rules.bullet_max_range {
+            "out_of_range"
+        } else {
+            continue;
+        };
+
+        if let Ok(mut event_sink) = query_event_sink.get_mut(bullet.tank) {
+            event_sink.queue.push(CTEvent {
+                event_type: "bullet_missed".to_string(),
+                info: json!({
+                    "reason": reason,
+                    "x": position.x,
+                    "y": position.y,
+                    "distance": distance,
+                    "ticks": ticks,
+                }),
+            });
+        }
+        commands.entity(bullet_entity).despawn_recursive();
+    }
+}
+
+/// Outward normal of the side of an axis aligned box that `point` is closest to.
+fn box_normal(center: Vec2, half_extents: Vec2, point: Vec2) -> Vec2 {
+    let offset = point - center;
+    let overshoot = offset.abs() - half_extents;
+    if overshoot.x > overshoot.y {
+        Vec2::new(offset.x.signum(), 0.)
+    } else {
+        Vec2::new(0., offset.y.signum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_normal_points_out_of_the_closest_side() {
+        let center = Vec2::new(100., 50.);
+        let half_extents = Vec2::new(40., 10.);
+
+        assert_eq!(
+            box_normal(center, half_extents, Vec2::new(145., 50.)),
+            Vec2::X
+        );
+        assert_eq!(
+            box_normal(center, half_extents, Vec2::new(55., 52.)),
+            -Vec2::X
+        );
+        assert_eq!(
+            box_normal(center, half_extents, Vec2::new(120., 65.)),
+            Vec2::Y
+        );
+        assert_eq!(
+            box_normal(center, half_extents, Vec2::new(80., 38.)),
+            -Vec2::Y
+        );
+    }
+
+    #[test]
+    fn box_normal_of_a_long_wall_ignores_the_distance_along_it() {
+        // the top wall of the arena
+        let center = Vec2::new(0., Game::HEIGHT / 2. + Game::WALL_THICKNESS);
+        let half_extents = Vec2::new(Game::WIDTH / 2., Game::WALL_THICKNESS);
+
+        let point = Vec2::new(450., Game::HEIGHT / 2. - 2.);
+        assert_eq!(box_normal(center, half_extents, point), -Vec2::Y);
+    }
 }
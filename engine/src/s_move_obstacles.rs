@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+use crate::c_obstacle::Obstacle;
+
+/// Moves kinematic obstacles `speed` pixels towards their next waypoint every tick.
+pub fn move_obstacles(mut query: Query<(&mut Obstacle, &mut Transform)>) {
+    for (mut obstacle, mut transform) in &mut query {
+        if obstacle.path.is_empty() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let target = obstacle.path[obstacle.next_waypoint];
+        let to_target = target - position;
+
+        let next_position = if to_target.length() <= obstacle.speed {
+            obstacle.next_waypoint = (obstacle.next_waypoint + 1) % obstacle.path.len();
+            target
+        } else {
+            position + to_target.normalize() * obstacle.speed
+        };
+
+        transform.translation.x = next_position.x;
+        transform.translation.y = next_position.y;
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::{
+    observer::{
+        MatchEnded, ObserverEntry, ObserverEvent, ObserverStream, ObstacleDestroyed,
+        PickupCollected, RadarScanned, ShotFired, TankDestroyed, TankHit,
+    },
+    TickState,
+};
+
+pub fn record_observer_stream(
+    state: Res<TickState>,
+    mut stream: ResMut<ObserverStream>,
+    mut shots_fired: EventReader<ShotFired>,
+    mut tanks_hit: EventReader<TankHit>,
+    mut radars_scanned: EventReader<RadarScanned>,
+    mut tanks_destroyed: EventReader<TankDestroyed>,
+    mut pickups_collected: EventReader<PickupCollected>,
+    mut obstacles_destroyed: EventReader<ObstacleDestroyed>,
+    mut matches_ended: EventReader<MatchEnded>,
+) {
+    let tick = state.count;
+
+    let events = shots_fired
+        .iter()
+        .cloned()
+        .map(ObserverEvent::ShotFired)
+        .chain(radars_scanned.iter().cloned().map(ObserverEvent::RadarScanned))
+        .chain(tanks_hit.iter().cloned().map(ObserverEvent::TankHit))
+        .chain(tanks_destroyed.iter().cloned().map(ObserverEvent::TankDestroyed))
+        .chain(pickups_collected.iter().cloned().map(ObserverEvent::PickupCollected))
+        .chain(obstacles_destroyed.iter().cloned().map(ObserverEvent::ObstacleDestroyed))
+        .chain(matches_ended.iter().cloned().map(ObserverEvent::MatchEnded))
+        .collect::<Vec<ObserverEvent>>();
+
+    stream
+        .entries
+        .extend(events.into_iter().map(|event| ObserverEntry { tick, event }));
+}
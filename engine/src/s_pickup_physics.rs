@@ -0,0 +1,76 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier2d::prelude::*;
+use serde_json::json;
+
+use crate::{
+    c_chassis::Chassis,
+    c_event::{CTEvent, EventSink},
+    c_health::Health,
+    c_pickup::{Pickup, PowerUps},
+    c_tank::TankId,
+    observer::PickupCollected,
+    rules::Rules,
+};
+
+pub fn pickup_physics(
+    mut commands: Commands,
+    mut contact_events: EventReader<CollisionEvent>,
+    rules: Res<Rules>,
+    mut query_tank: Query<(&TankId, &mut Health, &mut PowerUps, &mut EventSink, &Chassis)>,
+    query_pickup: Query<&Pickup>,
+    mut pickups_collected: EventWriter<PickupCollected>,
+) {
+    let mut collected = HashSet::new();
+
+    for contact_event in contact_events.iter() {
+        let CollisionEvent::Started(collision_entity_1, collision_entity_2, _event_flag) =
+            contact_event
+        else {
+            continue;
+        };
+
+        for (tank_entity, pickup_entity) in [
+            (collision_entity_1, collision_entity_2),
+            (collision_entity_2, collision_entity_1),
+        ] {
+            if collected.contains(pickup_entity) {
+                continue;
+            }
+            let Ok(pickup) = query_pickup.get(*pickup_entity) else {
+                continue;
+            };
+            let Ok((tank_id, mut health, mut power_ups, mut event_sink, chassis)) =
+                query_tank.get_mut(*tank_entity)
+            else {
+                continue;
+            };
+            if health.val == 0 {
+                continue;
+            }
+
+            match pickup {
+                Pickup::Repair => {
+                    health.val = (health.val + rules.pickup_repair).min(chassis.max_health());
+                }
+                Pickup::RapidFire => power_ups.rapid_fire = rules.pickup_duration,
+                Pickup::Shield => power_ups.shield = rules.pickup_duration,
+            }
+
+            event_sink.queue.push(CTEvent {
+                event_type: "pickup_collected".to_string(),
+                info: json!({
+                    "pickup": pickup,
+                    "health": health.val,
+                }),
+            });
+            pickups_collected.send(PickupCollected {
+                tank: *tank_entity,
+                tank_id: *tank_id,
+                pickup: *pickup,
+            });
+
+            commands.entity(*pickup_entity).despawn_recursive();
+            collected.insert(*pickup_entity);
+        }
+    }
+}
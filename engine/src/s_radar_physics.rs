@@ -1,84 +1,107 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_rapier2d::prelude::*;
+use serde_json::json;
 
 use crate::{
     c_event::{generate_event, EventSink},
-    c_tank::{Bullet, Radar, Tank},
+    c_obstacle::Obstacle,
+    map::{Map, TerrainKind},
+    c_pickup::Pickup,
+    c_radar_ghost::RadarGhost,
+    c_tank::{Bullet, Radar, Tank, TankId},
+    c_wreck::{Wreck, WreckMode},
+    observer::RadarScanned,
+    rules::Rules,
     CCollider, CollisionType, c_health::Health,
 };
 
 pub fn radar_physics(
     rapier_context: Res<RapierContext>,
     mut contact_events: EventReader<CollisionEvent>,
-    mut query_tank: Query<(Entity, &Tank, &mut EventSink, &Transform)>,
+    mut query_tank: Query<(Entity, &TankId, &Tank, &mut EventSink, &Transform)>,
     query_bullet: Query<&Bullet>,
     query_other_tank_health: Query<&Health, With<Tank>>,
     query_radar: Query<&mut Radar>,
+    query_ghost: Query<(Entity, &RadarGhost)>,
     query_collider: Query<(&CCollider, &Transform, Option<&Velocity>)>,
+    query_pickup: Query<&Pickup>,
+    query_obstacle: Query<&Obstacle>,
+    query_wreck: Query<&Wreck>,
+    map: Res<Map>,
+    rules: Res<Rules>,
+    mut radars_scanned: EventWriter<RadarScanned>,
 ) {
+    // radar or radar ghost entity -> (owning tank entity, radar entity)
+    let radar_owners = query_tank
+        .iter()
+        .map(|(tank_entity, _, tank, ..)| (tank.radar, (tank_entity, tank.radar)))
+        .chain(
+            query_ghost
+                .iter()
+                .map(|(ghost_entity, ghost)| (ghost_entity, (ghost.tank, ghost.radar))),
+        )
+        .collect::<HashMap<Entity, (Entity, Entity)>>();
+
     for contact_event in contact_events.iter() {
-        for (tank_entity, tank, mut event_sink, tank_transform) in &mut query_tank {
-            let radar = query_radar.get(tank.radar).unwrap();
+        let CollisionEvent::Started(collision_entity_1, collision_entity_2, _event_flag) =
+            contact_event
+        else {
+            continue;
+        };
 
-            if let CollisionEvent::Started(collision_entity_1, collision_entity_2, _event_flag) =
-                contact_event
-            {
-                if !radar.disabled {
-                    if collision_entity_1 == &tank.radar && *collision_entity_2 != tank_entity {
-                        if rapier_context
-                            .intersection_pair(*collision_entity_1, *collision_entity_2)
-                            == Some(true)
-                        {
-                            let (collider, scanned_entity_transform, scanned_entity_velocity) =
-                                query_collider.get(*collision_entity_2).unwrap();
-                            // info!("{:?} {:?}", tank_entity, state.tick);
-                            info!(
-                                "Tank Got Scan:{:?} Radar:{:?} Other:{:?}",
-                                tank_entity, tank.radar, collision_entity_2
-                            );
+        for (radar_entity, scanned_entity) in [
+            (collision_entity_1, collision_entity_2),
+            (collision_entity_2, collision_entity_1),
+        ] {
+            let Some(&(tank_entity, radar)) = radar_owners.get(radar_entity) else {
+                continue;
+            };
+            if *scanned_entity == tank_entity || query_radar.get(radar).unwrap().disabled {
+                continue;
+            }
+            if rapier_context.intersection_pair(*radar_entity, *scanned_entity) != Some(true) {
+                continue;
+            }
 
-                            on_radar_collision(
-                                &mut event_sink,
-                                &tank_entity,
-                                tank_transform,
-                                collision_entity_2,
-                                scanned_entity_transform,
-                                scanned_entity_velocity,
-                                &collider.collision_type,
-                                &query_bullet,
-                                &query_other_tank_health,
-                            );
-                        }
-                    } else if collision_entity_2 == &tank.radar
-                        && *collision_entity_1 != tank_entity
-                    {
-                        if rapier_context
-                            .intersection_pair(*collision_entity_1, *collision_entity_2)
-                            == Some(true)
-                        {
-                            let (collider, collider_transform, velocity) =
-                                query_collider.get(*collision_entity_1).unwrap();
-                            // info!("{:?} {:?}", tank_entity, state.tick);
-                            info!(
-                                "Tank Got Scan:{:?} Radar:{:?} Other:{:?}",
-                                tank_entity, tank.radar, collision_entity_1
-                            );
+            let (tank_entity, tank_id, _, mut event_sink, tank_transform) =
+                query_tank.get_mut(tank_entity).unwrap();
+            // e.g. a pickup collected earlier this tick
+            let Ok((collider, scanned_entity_transform, scanned_entity_velocity)) =
+                query_collider.get(*scanned_entity)
+            else {
+                continue;
+            };
 
-                            on_radar_collision(
-                                &mut event_sink,
-                                &tank_entity,
-                                tank_transform,
-                                collision_entity_1,
-                                collider_transform,
-                                velocity,
-                                &collider.collision_type,
-                                &query_bullet,
-                                &query_other_tank_health,
-                            );
-                        }
-                    }
-                }
+            if map.in_terrain(tank_transform.translation.truncate(), TerrainKind::Smoke)
+                || map.in_terrain(
+                    scanned_entity_transform.translation.truncate(),
+                    TerrainKind::Smoke,
+                )
+            {
+                continue;
             }
+            info!(
+                "Tank Got Scan:{:?} Radar:{:?} Other:{:?}",
+                tank_entity, radar_entity, scanned_entity
+            );
+
+            on_radar_collision(
+                &mut event_sink,
+                &tank_entity,
+                tank_id,
+                tank_transform,
+                scanned_entity,
+                scanned_entity_transform,
+                scanned_entity_velocity,
+                &collider.collision_type,
+                &query_bullet,
+                &query_other_tank_health,
+                &query_pickup,
+                &query_obstacle,
+                &query_wreck,
+                rules.wreck,
+                &mut radars_scanned,
+            );
         }
     }
 }
@@ -86,6 +109,7 @@ pub fn radar_physics(
 fn on_radar_collision(
     event_sink: &mut EventSink,
     tank_entity: &Entity,
+    tank_id: &TankId,
     _tank_transform: &Transform,
     scanned_entity: &Entity,
     scanned_entity_transform: &Transform,
@@ -93,7 +117,14 @@ fn on_radar_collision(
     collision_type: &CollisionType,
     query_bullet: &Query<&Bullet>,
     query_other_tank_health: &Query<&Health, With<Tank>>,
+    query_pickup: &Query<&Pickup>,
+    query_obstacle: &Query<&Obstacle>,
+    query_wreck: &Query<&Wreck>,
+    wreck_mode: WreckMode,
+    radars_scanned: &mut EventWriter<RadarScanned>,
 ) {
+    let mut wreck = query_wreck.contains(*scanned_entity);
+
     if *collision_type == CollisionType::Bullet {
         let bullet = query_bullet.get(*scanned_entity).unwrap();
 
@@ -104,19 +135,39 @@ fn on_radar_collision(
     } else if *collision_type == CollisionType::Tank {
         let other_tank_health = query_other_tank_health.get(*scanned_entity).unwrap();
         if other_tank_health.val <= 0 {
-            // SKIP SCAN IF OTHER TANK IS DEAD
-            return;
+            if wreck_mode != WreckMode::Remain {
+                // SKIP SCAN IF OTHER TANK IS DEAD, update_wrecks is about to convert it
+                return;
+            }
+            // the body stays in the arena as is, so radar still sees it
+            wreck = true;
         }
     }
 
     info!("SCANNED {:?} of type {:?}", scanned_entity, collision_type);
 
-    generate_event(
+    radars_scanned.send(RadarScanned {
+        tank: *tank_entity,
+        tank_id: *tank_id,
+        scanned: *scanned_entity,
+        collision_type: *collision_type,
+    });
+
+    let mut event = generate_event(
         "radar_scan".to_string(),
-        event_sink,
         scanned_entity,
         scanned_entity_transform,
         scanned_entity_velocity,
         collision_type,
     );
+    if let Ok(pickup) = query_pickup.get(*scanned_entity) {
+        event.info["pickup"] = json!(pickup);
+    }
+    if let Ok(obstacle) = query_obstacle.get(*scanned_entity) {
+        event.info["obstacle"] = obstacle.to_json();
+    }
+    if wreck {
+        event.info["wreck"] = true.into();
+    }
+    event_sink.queue.push(event);
 }
@@ -6,6 +6,8 @@ use crate::{c_client::Client, c_command_source::CommandSource, c_health::Health}
 pub fn request_commands(mut query: Query<(&mut CommandSource, &mut Client, &Health)>) {
     // println!("request commands");
     for (mut command_receiver, mut client_connection, health) in &mut query {
+        client_connection.client.on_tick();
+
         if command_receiver.queue.is_empty() {
             if health.val == 0 {
                 command_receiver.queue.push(Commands::NONE);
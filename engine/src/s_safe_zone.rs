@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::{
+    c_event::{CTEvent, EventSink},
+    c_health::Health,
+    c_pickup::PowerUps,
+    c_radar_needs_update::RadarNeedsUpdate,
+    c_tank::{Tank, TankId},
+    observer::TankDestroyed,
+    rules::Rules,
+    seeded_random, Game, TickState,
+};
+
+/// The battle royale zone. Tanks outside of it take damage once it starts shrinking.
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct SafeZone {
+    pub active: bool,
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl SafeZone {
+    /// The zone at `tick`, the same for everyone who knows the rules, including replays.
+    pub fn at(rules: &Rules, tick: u32) -> SafeZone {
+        if !rules.safe_zone || tick < rules.safe_zone_start_tick {
+            return SafeZone::default();
+        }
+
+        // the final zone lies somewhere in the middle half of the arena
+        let r = seeded_random(rules.seed, u64::MAX);
+        let center = Vec2::new(
+            Game::WIDTH / 2. * (((r & 0xFFFF) as f32 / 0xFFFF as f32) - 0.5),
+            Game::HEIGHT / 2. * ((((r >> 16) & 0xFFFF) as f32 / 0xFFFF as f32) - 0.5),
+        );
+
+        let start_radius = Vec2::new(Game::WIDTH, Game::HEIGHT).length();
+        let progress = if rules.safe_zone_shrink_ticks == 0 {
+            1.0
+        } else {
+            ((tick - rules.safe_zone_start_tick) as f32 / rules.safe_zone_shrink_ticks as f32)
+                .min(1.0)
+        };
+
+        SafeZone {
+            active: true,
+            center,
+            radius: start_radius + (rules.safe_zone_min_radius - start_radius) * progress,
+        }
+    }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        !self.active || position.distance(self.center) <= self.radius
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        if !self.active {
+            return serde_json::Value::Null;
+        }
+        json!({
+            "x": self.center.x,
+            "y": self.center.y,
+            "radius": self.radius,
+        })
+    }
+}
+
+pub fn update_safe_zone(
+    mut commands: Commands,
+    state: Res<TickState>,
+    rules: Res<Rules>,
+    mut zone: ResMut<SafeZone>,
+    mut query: Query<(
+        Entity,
+        &TankId,
+        &Tank,
+        &Transform,
+        &mut Health,
+        &mut EventSink,
+        &PowerUps,
+    )>,
+    mut tanks_destroyed: EventWriter<TankDestroyed>,
+) {
+    let next_zone = SafeZone::at(&rules, state.count);
+    let started = next_zone.active && !zone.active;
+    if *zone != next_zone {
+        *zone = next_zone;
+    }
+
+    if !zone.active {
+        return;
+    }
+
+    let damage_tick =
+        rules.safe_zone_damage_interval > 0 && state.count % rules.safe_zone_damage_interval == 0;
+
+    for (entity, tank_id, tank, transform, mut health, mut event_sink, power_ups) in &mut query {
+        if started {
+            event_sink.queue.push(CTEvent {
+                event_type: "safe_zone".to_string(),
+                info: zone.to_json(),
+            });
+        }
+
+        // shields block zone damage like any other
+        if !damage_tick
+            || health.val == 0
+            || power_ups.shield > 0
+            || zone.contains(transform.translation.truncate())
+        {
+            continue;
+        }
+
+        health.val = (health.val - rules.safe_zone_damage).max(0);
+
+        let mut info = zone.to_json();
+        info["damage"] = rules.safe_zone_damage.into();
+        info["health"] = health.val.into();
+        event_sink.queue.push(CTEvent {
+            event_type: "safe_zone_damage".to_string(),
+            info,
+        });
+
+        if health.val == 0 {
+            commands.entity(tank.radar).insert(RadarNeedsUpdate);
+            tanks_destroyed.send(TankDestroyed {
+                tank: entity,
+                tank_id: *tank_id,
+                by: None,
+            });
+        }
+    }
+}
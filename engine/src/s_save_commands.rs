@@ -1,7 +1,7 @@
-use bevy::{prelude::*, utils::HashSet};
+use bevy::{prelude::*, utils::{HashMap, HashSet}};
 use ct_api::Commands;
-use crate::{TickState, c_tank::{AllTankInfo, Tank, Radar, Gun, DamageDealer}, c_command_source::CommandSource, c_health::Health, MaxSimulationTicks};
-use serde_json::{json, to_value};
+use crate::{TickState, c_chassis::Chassis, c_tank::{AllTankInfo, Tank, TankId, DamageDealer}, c_tank_stats::TankStats, c_command_source::CommandSource, c_health::Health, MaxSimulationTicks, observer::MatchEnded, game_mode::{ActiveGameMode, GameMode}, s_update_game_mode::mode_tanks};
+use serde_json::{json, to_value, Value};
 use std::{fs::OpenOptions, io::Write};
 
 // use crate::{c_command_source::CommandSource, c_health::Health, c_tank::*, TickState};
@@ -11,28 +11,39 @@ pub fn save_commands(
     mut state: ResMut<TickState>,
     tank_state: Res<AllTankInfo>,
     mut exit: EventWriter<AppExit>,
-    query: Query<&CommandSource>,
-    tanks: Query<(&Transform, &Tank)>,
-    radars: Query<&Transform, With<Radar>>,
-    guns: Query<&Transform, With<Gun>>,
-    healths: Query<&Health, With<Tank>>,
-    damage_dealt: Query<&DamageDealer, With<Tank>>,
+    tanks: Query<(&TankId, &Tank, &Transform, &CommandSource, &Health, &DamageDealer, &TankStats, &Chassis)>,
+    parts: Query<(&TankId, &Transform), Without<Tank>>,
     max_ticks: Res<MaxSimulationTicks>,
+    mode: Res<ActiveGameMode>,
+    mut matches_ended: EventWriter<MatchEnded>,
 ) {
-    let tanks: Vec<(&Transform, &Tank)> = tanks.iter().collect();
-    let radars: Vec<&Transform> = radars.iter().collect();
-    let guns: Vec<&Transform> = guns.iter().collect();
-    let healths: Vec<&Health> = healths.iter().collect();
-    let damages_dealt: Vec<&DamageDealer> = damage_dealt.iter().collect();
+    let tanks: HashMap<TankId, _> = tanks.iter().map(|f| (*f.0, f)).collect();
 
     let mut f = OpenOptions::new()
         .append(true)
         .open("./sim.txt")
         .expect("Unable to open file");
 
-    let mut dead_count = 0usize;
+    let mut healths = vec![0; tank_state.all.len()];
+    let mut damages_dealt = vec![0; tank_state.all.len()];
+    let mut stats = vec![TankStats::default(); tank_state.all.len()];
+    let mut chassis = vec![Chassis::default(); tank_state.all.len()];
+
+    // one line per tank in TankInfo.index order, which is how readers split the lines
+    for tank_info in &tank_state.all {
+        let tank_id = TankId(tank_info.index);
+
+        let Some(&(_, tank, transform, command_receiver, health, damage_dealer, tank_stats, tank_chassis)) = tanks.get(&tank_id) else {
+            // despawned tanks keep their line so the following ones don't shift
+            f.write_all(format!("{}|\n", Commands::NONE).as_bytes())
+                .expect("Unable to write data");
+            continue;
+        };
+
+        let (radar_id, radar) = parts.get(tank.radar).unwrap();
+        let (gun_id, gun) = parts.get(tank.gun).unwrap();
+        debug_assert!(*radar_id == tank_id && *gun_id == tank_id);
 
-    for (i, command_receiver) in query.iter().enumerate() {
         let grouped_commands = if command_receiver.queue.is_empty() {
             Commands::NONE
         } else {
@@ -45,72 +56,122 @@ pub fn save_commands(
             format!(
                 "{}|{},{},{},{},{},{},{},{}\n", // ,{},{},{},{},{},{}\n",
                 grouped_commands,
-                tanks[i].0.translation.x,
-                tanks[i].0.translation.y,
-                // tanks[i].0.rotation.x, // unused
-                // tanks[i].0.rotation.y, // unused
-                tanks[i].0.rotation.z,
-                tanks[i].0.rotation.w,
-                // radars[i].rotation.x, // unused
-                // radars[i].rotation.y, // unused
-                radars[i].rotation.z,
-                radars[i].rotation.w,
-                // guns[i].rotation.x, // unused
-                // guns[i].rotation.y, // unused
-                guns[i].rotation.z,
-                guns[i].rotation.w,
+                transform.translation.x,
+                transform.translation.y,
+                // transform.rotation.x, // unused
+                // transform.rotation.y, // unused
+                transform.rotation.z,
+                transform.rotation.w,
+                // radar.rotation.x, // unused
+                // radar.rotation.y, // unused
+                radar.rotation.z,
+                radar.rotation.w,
+                // gun.rotation.x, // unused
+                // gun.rotation.y, // unused
+                gun.rotation.z,
+                gun.rotation.w,
             )
             .to_string()
             .as_bytes(),
         )
         .expect("Unable to write data");
 
-        if healths[i].val <= 0 {
-            dead_count += 1;
-        }
+        healths[tank_id.0] = health.val;
+        damages_dealt[tank_id.0] = damage_dealer.damage_dealt;
+        stats[tank_id.0] = tank_stats.clone();
+        chassis[tank_id.0] = *tank_chassis;
 
         // println!("commands remaining {:?}", command_receiver.queue);
     }
 
-    let early_stop = dead_count >= tanks.len() - 1 && tanks.len() > 1;
+    let mode_tanks = mode_tanks(
+        tank_state.all.len(),
+        tanks.values().map(|f| (f.0, f.2, f.4, f.5)),
+    );
+    let early_stop = mode.0.is_over(&mode_tanks);
 
     if state.count >= max_ticks.0 || early_stop {
         state.count = max_ticks.0;
         println!("early_stop: {}", early_stop);
-        // TODO save results of the simulation (winner, damage given, damage taken, time alive)
-        let mut j = json!({});
-        let mut best_idx: usize = 0;
-        let mut dup: bool = false;
-        for tank_info in &tank_state.all
-        {
-            let dmg = damages_dealt[tank_info.index].damage_dealt;
-
-            j[tank_info.container_name.to_string()] = json!({
-                "tank_hash": tank_info.hash,
-                "index": tank_info.index,
-                "health": healths[tank_info.index].val,
-                "damage_given": dmg,
-            });
-
-            if dmg == damages_dealt[best_idx].damage_dealt && tank_info.index != 0 {
-                dup = true;
-            }
-            if dmg > damages_dealt[best_idx].damage_dealt {
-                dup = false;
-                best_idx = tank_info.index;
-            }
-        }
-        j["tanks"] = to_value(HashSet::from_iter(tank_state.all.iter().map(|f| f.hash.to_string()))).unwrap();
-        j["winner"] = if dup {
-            "".into()
-        } else {
-            tank_state.all[best_idx].container_name.to_string().into()
-        };
-        j["winner_index"] = if dup { (-1i32).into() } else { best_idx.into() };
+        let j = match_results(
+            &tank_state,
+            &healths,
+            &damages_dealt,
+            &stats,
+            &chassis,
+            mode.0.as_ref(),
+            &mode.0.scores(&mode_tanks),
+        );
         println!("{}", j);
 
         f.write_all(j.to_string().as_bytes())
             .expect("Unable to write data");
+        matches_ended.send(MatchEnded {
+            tick: state.count,
+            results: j,
+        });
         exit.send(AppExit);
     }
 }
+
+// TODO save results of the simulation (winner, damage given, damage taken, time alive)
+pub fn match_results(
+    tank_state: &AllTankInfo,
+    healths: &[i32],
+    damages_dealt: &[u32],
+    stats: &[TankStats],
+    chassis: &[Chassis],
+    mode: &dyn GameMode,
+    scores: &[i64],
+) -> Value {
+    let mut j = json!({});
+    for tank_info in &tank_state.all
+    {
+        let score = scores[tank_info.index];
+
+        j[tank_info.container_name.to_string()] = json!({
+            "tank_hash": tank_info.hash,
+            "index": tank_info.index,
+            "health": healths[tank_info.index],
+            "damage_given": damages_dealt[tank_info.index],
+            "score": score,
+            "stats": stats[tank_info.index].to_json(),
+            "chassis": chassis[tank_info.index],
+        });
+    }
+    j["tanks"] = to_value(HashSet::from_iter(tank_state.all.iter().map(|f| f.hash.to_string()))).unwrap();
+    let winner = mode.winner(scores);
+    j["winner"] = match winner {
+        Some(index) => tank_state.all[index].container_name.to_string().into(),
+        None => "".into(),
+    };
+    j["winner_index"] = match winner {
+        Some(index) => index.into(),
+        None => (-1i32).into(),
+    };
+    j["mode"] = mode.kind().name().into();
+    j["mode_results"] = mode.results();
+
+    // every participant ranked by the mode's score, then damage given, then health left,
+    // then time alive; tanks that tie on all of them share a rank
+    let score = |i: usize| (scores[i], damages_dealt[i], healths[i], stats[i].ticks_alive);
+    let mut ranking = tank_state.all.iter().map(|f| f.index).collect::<Vec<usize>>();
+    ranking.sort_by(|a, b| score(*b).cmp(&score(*a)).then(a.cmp(b)));
+
+    for (position, &i) in ranking.iter().enumerate() {
+        let rank = match position {
+            0 => 1.into(),
+            _ if score(ranking[position - 1]) == score(i) => {
+                j[tank_state.all[ranking[position - 1]].container_name.to_string()]["rank"].clone()
+            }
+            _ => (position + 1).into(),
+        };
+        j[tank_state.all[i].container_name.to_string()]["rank"] = rank;
+    }
+    j["ranking"] = ranking
+        .iter()
+        .map(|&i| tank_state.all[i].container_name.to_string())
+        .collect::<Vec<String>>()
+        .into();
+    j
+}
@@ -0,0 +1,164 @@
+use std::{fs::File, io::BufWriter};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use ct_api::Commands;
+use serde_json::to_value;
+
+use crate::{
+    c_command_source::CommandSource,
+    c_event::EventSink,
+    c_health::Health,
+    c_obstacle::Obstacle,
+    c_tank::{Bullet, Radar, Tank, TankId, TankInfo},
+    map::Map,
+    observer::MatchEnded,
+    replay::{
+        z_rotation, BulletFrame, EventFrame, Frame, ObstacleFrame, ReplayHeader, ReplayWriter,
+        TankFrame,
+    },
+    rules::Rules,
+    TickState,
+};
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    writer: Option<ReplayWriter<BufWriter<File>>>,
+    pending_events: Vec<EventFrame>,
+}
+
+impl ReplayRecorder {
+    pub fn create(
+        path: &str,
+        tanks: &[TankInfo],
+        max_ticks: u32,
+        rules: &Rules,
+        map: &Map,
+    ) -> ReplayRecorder {
+        let mut header_rules = to_value(rules).unwrap();
+        header_rules["max_ticks"] = max_ticks.into();
+        header_rules["dt"] = TickState::DT.into();
+
+        let header = ReplayHeader {
+            rules: header_rules,
+            map: map.to_header(),
+            seed: rules.seed,
+            tanks: tanks.to_vec(),
+        };
+        let file = File::create(path).expect("Unable to create file");
+
+        ReplayRecorder {
+            writer: Some(
+                ReplayWriter::new(BufWriter::new(file), &header).expect("Unable to write data"),
+            ),
+            pending_events: vec![],
+        }
+    }
+}
+
+/// Copies events before `request_commands_by_event` hands them to the tanks and clears them.
+pub fn record_replay_events(
+    mut recorder: ResMut<ReplayRecorder>,
+    query: Query<(&TankId, &EventSink)>,
+) {
+    for (tank_id, event_sink) in &query {
+        for event in event_sink.queue.iter() {
+            recorder.pending_events.push(EventFrame {
+                tank: tank_id.0,
+                event_type: event.event_type.to_string(),
+                info: event.info.clone(),
+            });
+        }
+    }
+}
+
+pub fn save_replay(
+    state: Res<TickState>,
+    mut recorder: ResMut<ReplayRecorder>,
+    query_tank: Query<(&TankId, &Tank, &Transform, &Health, &CommandSource)>,
+    query_transform: Query<&Transform, Without<Tank>>,
+    query_radar: Query<&Radar>,
+    query_bullet: Query<(Entity, &Bullet, &Transform, &Velocity)>,
+    query_obstacle: Query<(&Obstacle, &Transform)>,
+    mut matches_ended: EventReader<MatchEnded>,
+) {
+    if recorder.writer.is_none() {
+        return;
+    }
+
+    let mut tanks = query_tank
+        .iter()
+        .map(|(tank_id, tank, transform, health, command_source)| TankFrame {
+            index: tank_id.0,
+            commands: command_source
+                .queue
+                .first()
+                .copied()
+                .unwrap_or(Commands::NONE),
+            x: transform.translation.x,
+            y: transform.translation.y,
+            rotation: z_angle(transform),
+            gun_rotation: z_angle(query_transform.get(tank.gun).unwrap()),
+            radar_rotation: z_angle(query_transform.get(tank.radar).unwrap()),
+            health: health.val,
+            radar_disabled: query_radar.get(tank.radar).unwrap().disabled,
+        })
+        .collect::<Vec<TankFrame>>();
+    tanks.sort_by_key(|f| f.index);
+
+    let bullets = query_bullet
+        .iter()
+        .filter_map(|(entity, bullet, transform, velocity)| {
+            let (shooter, ..) = query_tank.get(bullet.tank).ok()?;
+            Some(BulletFrame {
+                id: entity.index(),
+                tank: shooter.0,
+                x: transform.translation.x,
+                y: transform.translation.y,
+                vx: velocity.linvel.x,
+                vy: velocity.linvel.y,
+                bounces: bullet.bounces,
+            })
+        })
+        .collect();
+
+    let mut obstacles = query_obstacle
+        .iter()
+        .map(|(obstacle, transform)| ObstacleFrame {
+            index: obstacle.index,
+            x: transform.translation.x,
+            y: transform.translation.y,
+            health: obstacle.health,
+        })
+        .collect::<Vec<ObstacleFrame>>();
+    obstacles.sort_by_key(|f| f.index);
+
+    let frame = Frame {
+        tick: state.count,
+        tanks,
+        bullets,
+        events: recorder.pending_events.drain(..).collect(),
+        obstacles,
+    };
+
+    recorder
+        .writer
+        .as_mut()
+        .unwrap()
+        .write_frame(&frame)
+        .expect("Unable to write data");
+
+    // `save_commands` runs first and ends the match with the results
+    if let Some(ended) = matches_ended.iter().last() {
+        recorder
+            .writer
+            .take()
+            .unwrap()
+            .finish(&ended.results)
+            .expect("Unable to write data");
+    }
+}
+
+fn z_angle(transform: &Transform) -> f32 {
+    z_rotation(transform.rotation.z, transform.rotation.w)
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{c_obstacle::Obstacle, map::Map, CCollider, CollisionMask, CollisionType};
+
+pub fn setup_obstacles(mut commands: Commands, map: Res<Map>) {
+    for (index, def) in map.obstacles.iter().enumerate() {
+        let mut obstacle = commands.spawn((
+            Obstacle {
+                index,
+                width: def.width,
+                height: def.height,
+                health: def.health,
+                path: def.path.iter().map(|f| Vec2::from(*f)).collect(),
+                speed: def.speed,
+                next_waypoint: 0,
+            },
+            CCollider {
+                collision_type: CollisionType::Obstacle,
+            },
+            ActiveEvents::COLLISION_EVENTS,
+            Collider::cuboid(def.width / 2., def.height / 2.),
+            // obstacles block the same things walls do
+            CollisionGroups::new(
+                Group::from_bits_truncate(CollisionMask::WALL),
+                Group::from_bits_truncate(
+                    CollisionMask::TANK | CollisionMask::BULLET | CollisionMask::RADAR,
+                ),
+            ),
+            SpatialBundle {
+                transform: Transform::from_xyz(def.x, def.y, 1.0),
+                visibility: Visibility::Visible,
+                ..default()
+            },
+        ));
+
+        if def.path.is_empty() {
+            obstacle.insert(RigidBody::Fixed);
+        } else {
+            obstacle.insert(RigidBody::KinematicPositionBased);
+        }
+    }
+}
@@ -1,12 +1,17 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::{Game, CCollider, CollisionMask, CollisionType};
+use crate::{rules::Rules, Game, CCollider, CollisionMask, CollisionType};
+
+pub fn setup_walls(mut commands: Commands, rules: Res<Rules>) {
+    // tanks and bullets wrap around to the opposite edge instead
+    if rules.wrap_around {
+        return;
+    }
 
-pub fn setup_walls(mut commands: Commands) {
     /* Create the ground. */
 
-    const WALL_THICKNESS: f32 = 10.;
+    const WALL_THICKNESS: f32 = Game::WALL_THICKNESS;
 
     commands.spawn((
         CCollider {
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    c_pickup::Pickup, map::Map, rules::Rules, seeded_random, CCollider, CollisionMask,
+    CollisionType, Game, TickState,
+};
+
+/// Spawns a pickup every `Rules::pickup_interval` ticks at a place and of a kind
+/// derived from `Rules::seed` and the tick, so a match replays identically. Places
+/// inside an obstacle are moved clear of it.
+pub fn spawn_pickups(
+    mut commands: Commands,
+    state: Res<TickState>,
+    rules: Res<Rules>,
+    map: Res<Map>,
+    query: Query<&Pickup>,
+) {
+    if rules.pickup_interval == 0
+        || state.count == 0
+        || state.count % rules.pickup_interval != 0
+        || query.iter().count() >= rules.max_pickups as usize
+    {
+        return;
+    }
+
+    const MARGIN: f32 = 50.0;
+
+    let r = seeded_random(rules.seed, state.count as u64);
+    let kind = Pickup::ALL[(r % Pickup::ALL.len() as u64) as usize];
+    let x = ((r >> 8) & 0xFFFF) as f32 / 0xFFFF as f32;
+    let y = ((r >> 24) & 0xFFFF) as f32 / 0xFFFF as f32;
+    let position = map.clear_position(
+        Vec2::new(
+            (Game::WIDTH - 2. * MARGIN) * (x - 0.5),
+            (Game::HEIGHT - 2. * MARGIN) * (y - 0.5),
+        ),
+        Pickup::RADIUS,
+    );
+
+    commands.spawn((
+        kind,
+        CCollider {
+            collision_type: CollisionType::Pickup,
+        },
+        ActiveEvents::COLLISION_EVENTS,
+        Sensor,
+        RigidBody::Fixed,
+        Collider::ball(Pickup::RADIUS),
+        CollisionGroups::new(
+            Group::from_bits_truncate(CollisionMask::PICKUP),
+            Group::from_bits_truncate(CollisionMask::TANK | CollisionMask::RADAR),
+        ),
+        SpatialBundle {
+            transform: Transform::from_xyz(position.x, position.y, 2.0),
+            visibility: Visibility::Visible,
+            ..default()
+        },
+    ));
+}
@@ -1,134 +1,268 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde_json::json;
 
 use crate::{
     c_event::{generate_event, EventSink},
     c_health::Health,
-    c_tank::{Bullet, DamageDealer, Tank},
+    c_pickup::PowerUps,
+    c_tank::{Bullet, DamageDealer, Tank, TankId},
+    c_wreck::Wreck,
+    observer::{TankDestroyed, TankHit},
+    rules::Rules,
     CCollider, CollisionType, c_radar_needs_update::RadarNeedsUpdate,
 };
 
 pub fn tank_physics(
     mut contact_events: EventReader<CollisionEvent>,
-    mut query_tank_many: Query<(Entity, &Tank, &mut Health, &Transform, &Velocity)>,
+    // wrecks no longer take hits
+    mut query_tank_many: Query<
+        (
+            Entity,
+            &TankId,
+            &Tank,
+            &mut Health,
+            &Transform,
+            &Velocity,
+            &PowerUps,
+        ),
+        Without<Wreck>,
+    >,
+    query_tank_id: Query<&TankId, With<Tank>>,
+    query_tank: Query<&Tank>,
     mut query_event_sink: Query<&mut EventSink>,
     mut query_damage_dealer: Query<&mut DamageDealer>,
     query_bullet: Query<&Bullet>,
     query_collidable: Query<(&CCollider, &Transform, Option<&Velocity>)>,
     mut commands: Commands,
+    mut tanks_hit: EventWriter<TankHit>,
+    mut tanks_destroyed: EventWriter<TankDestroyed>,
+    rules: Res<Rules>,
+    rapier_context: Res<RapierContext>,
 ) {
     for contact_event in contact_events.iter() {
-        for (tank_entity, tank, mut tank_health, tank_transform, tank_velocity) in
-            &mut query_tank_many
-        {
-            if let CollisionEvent::Started(collision_entity_1, collision_entity_2, _event_flag) =
-                contact_event
-            {
-                if collision_entity_1 == &tank_entity {
-                    let (collider, collided_entity_transform, collided_entity_velocity) =
-                        query_collidable.get(*collision_entity_2).unwrap();
-                    on_tank_collision(
-                        &tank_entity,
-                        tank,
-                        tank_transform,
-                        tank_velocity,
-                        &mut tank_health,
-                        collision_entity_2,
-                        collided_entity_transform,
-                        collided_entity_velocity,
-                        &collider.collision_type,
-                        &mut query_damage_dealer,
-                        &query_bullet,
-                        &mut query_event_sink,
-                        &mut commands,
-                    );
-                } else if collision_entity_2 == &tank_entity {
-                    let (collider, collided_entity_transform, collided_entity_velocity) =
-                        query_collidable.get(*collision_entity_1).unwrap();
-                    on_tank_collision(
-                        &tank_entity,
-                        tank,
-                        tank_transform,
-                        tank_velocity,
-                        &mut tank_health,
-                        collision_entity_1,
-                        collided_entity_transform,
-                        collided_entity_velocity,
-                        &collider.collision_type,
-                        &mut query_damage_dealer,
-                        &query_bullet,
-                        &mut query_event_sink,
-                        &mut commands,
-                    );
-                }
-            }
+        let CollisionEvent::Started(collision_entity_1, collision_entity_2, _event_flag) =
+            contact_event
+        else {
+            continue;
+        };
+
+        // dispatch straight to whichever of the two entities is a tank
+        for (entity, collided_entity) in [
+            (collision_entity_1, collision_entity_2),
+            (collision_entity_2, collision_entity_1),
+        ] {
+            let Ok((
+                tank_entity,
+                tank_id,
+                tank,
+                mut tank_health,
+                tank_transform,
+                tank_velocity,
+                power_ups,
+            )) = query_tank_many.get_mut(*entity)
+            else {
+                continue;
+            };
+            let Ok((collider, collided_entity_transform, collided_entity_velocity)) =
+                query_collidable.get(*collided_entity)
+            else {
+                continue;
+            };
+
+            // the events come from the last physics step, which tanks took at last tick's velocity
+            let other_velocity = match query_tank.get(*collided_entity) {
+                Ok(other_tank) => other_tank.last_velocity,
+                Err(_) => collided_entity_velocity.map_or(Vec2::ZERO, |f| f.linvel),
+            };
+            let towards = contact_normal(&rapier_context, tank_entity, *collided_entity)
+                .unwrap_or_else(|| {
+                    (collided_entity_transform.translation - tank_transform.translation)
+                        .truncate()
+                        .normalize_or_zero()
+                });
+
+            on_tank_collision(
+                &tank_entity,
+                tank_id,
+                tank,
+                tank_transform,
+                tank_velocity,
+                &mut tank_health,
+                power_ups.shield > 0,
+                collided_entity,
+                collided_entity_transform,
+                collided_entity_velocity,
+                other_velocity,
+                towards,
+                &collider.collision_type,
+                &mut query_damage_dealer,
+                &query_bullet,
+                &query_tank_id,
+                &mut query_event_sink,
+                &mut commands,
+                &mut tanks_hit,
+                &mut tanks_destroyed,
+                &rules,
+            );
         }
     }
 }
 
 fn on_tank_collision(
     tank_entity: &Entity,
+    tank_id: &TankId,
     tank: &Tank,
     tank_transform: &Transform,
     tank_velocity: &Velocity,
     tank_health: &mut Health,
+    shielded: bool,
     collided_entity: &Entity,
     collided_entity_transform: &Transform,
     collided_entity_velocity: Option<&Velocity>,
+    other_velocity: Vec2,
+    towards: Vec2,
     collision_type: &CollisionType,
     query_damage_dealer: &mut Query<&mut DamageDealer>,
     query_bullet: &Query<&Bullet>,
+    query_tank_id: &Query<&TankId, With<Tank>>,
     query_event_sink: &mut Query<&mut EventSink>,
     commands: &mut Commands,
+    tanks_hit: &mut EventWriter<TankHit>,
+    tanks_destroyed: &mut EventWriter<TankDestroyed>,
+    rules: &Rules,
 ) {
+    let was_alive = tank_health.val > 0;
+    let mut by_tank = None;
+    let mut damage = 0;
+
+    // how fast each side was closing in along the contact normal, walls only get hit by the
+    // tank itself
+    let own_approach = tank.last_velocity.dot(towards).max(0.);
+    let other_approach = other_velocity.dot(-towards).max(0.);
+    let impact_speed = match *collision_type {
+        CollisionType::Tank => own_approach + other_approach,
+        _ => own_approach,
+    };
+    let collision_damage = if shielded {
+        0
+    } else if rules.velocity_collision_damage {
+        ((impact_speed * rules.collision_damage_per_speed).round() as i32)
+            .min(rules.max_collision_damage)
+    } else {
+        Tank::COLLISION_DAMAGE
+    };
+    let mut rammed = None;
+
     match *collision_type {
-        CollisionType::Radar => {
+        CollisionType::Radar | CollisionType::Pickup => {
             return;
         }
         CollisionType::Bullet => {
-            let tank_entity_that_shot_this_bullet =
-                query_bullet.get(*collided_entity).unwrap().tank;
-            let damage_dealer = &mut query_damage_dealer
-                .get_mut(tank_entity_that_shot_this_bullet)
-                .unwrap();
-            damage_dealer.damage_dealt += 10;
-
-            generate_event(
-                "bullet_hit".to_string(),
-                &mut query_event_sink
+            let bullet = query_bullet.get(*collided_entity).unwrap();
+            let tank_entity_that_shot_this_bullet = bullet.tank;
+            if !shielded {
+                damage = bullet.damage(rules);
+            }
+            tank_health.val -= damage;
+
+            // a ricochet coming back to its own tank is nobody's credit
+            if tank_entity_that_shot_this_bullet != *tank_entity {
+                let damage_dealer = &mut query_damage_dealer
                     .get_mut(tank_entity_that_shot_this_bullet)
-                    .unwrap(),
-                tank_entity,
-                tank_transform,
-                Some(tank_velocity),
-                &CollisionType::Tank,
-            );
-            tank_health.val -= 10;
+                    .unwrap();
+                damage_dealer.damage_dealt += damage as u32;
+
+                query_event_sink
+                    .get_mut(tank_entity_that_shot_this_bullet)
+                    .unwrap()
+                    .queue
+                    .push(generate_event(
+                        "bullet_hit".to_string(),
+                        tank_entity,
+                        tank_transform,
+                        Some(tank_velocity),
+                        &CollisionType::Tank,
+                    ));
+                by_tank = query_tank_id.get(tank_entity_that_shot_this_bullet).ok().copied();
+            }
         }
         CollisionType::Tank => {
-            tank_health.val -= 10;
+            damage = collision_damage;
+            tank_health.val -= damage;
+
+            // only a tank that was driving into this one gets the credit
+            if other_approach > 0. {
+                by_tank = query_tank_id.get(*collided_entity).ok().copied();
+                if let Ok(mut damage_dealer) = query_damage_dealer.get_mut(*collided_entity) {
+                    damage_dealer.damage_dealt += damage as u32;
+                }
+            }
+            if own_approach > 0. {
+                rammed = query_tank_id.get(*collided_entity).ok().copied();
+            }
         }
-        CollisionType::Wall => {
-            tank_health.val -= 10;
+        CollisionType::Wall | CollisionType::Obstacle => {
+            damage = collision_damage;
+            tank_health.val -= damage;
         }
     };
 
-    println!(
-        "HIT {:?}, by {:?} of type {:?}",
-        tank_entity, collided_entity, collision_type
-    );
-
     if tank_health.val <= 0 {
         tank_health.val = 0;
         commands.entity(tank.radar).insert(RadarNeedsUpdate);
     }
 
-    generate_event(
+    tanks_hit.send(TankHit {
+        tank: *tank_entity,
+        tank_id: *tank_id,
+        by: *collided_entity,
+        collision_type: *collision_type,
+        by_tank,
+        damage,
+        health: tank_health.val,
+        shielded,
+    });
+
+    if was_alive && tank_health.val == 0 {
+        tanks_destroyed.send(TankDestroyed {
+            tank: *tank_entity,
+            tank_id: *tank_id,
+            by: by_tank,
+        });
+    }
+
+    let mut event = generate_event(
         "tank_hit".to_string(),
-        &mut query_event_sink.get_mut(*tank_entity).unwrap(),
         collided_entity,
         collided_entity_transform,
         collided_entity_velocity,
         collision_type,
     );
+    event.info["damage"] = damage.into();
+    event.info["health"] = tank_health.val.into();
+    if *collision_type != CollisionType::Bullet {
+        event.info["impact_speed"] = impact_speed.into();
+        event.info["rammed_by"] = json!(by_tank.map(|f| f.0));
+        event.info["rammed"] = json!(rammed.map(|f| f.0));
+    }
+    query_event_sink
+        .get_mut(*tank_entity)
+        .unwrap()
+        .queue
+        .push(event);
+}
+
+/// Normal of the contact between two colliders, pointing from `entity` towards `other`.
+fn contact_normal(rapier_context: &RapierContext, entity: Entity, other: Entity) -> Option<Vec2> {
+    let pair = rapier_context.contact_pair(entity, other)?;
+    let normal = pair
+        .manifolds()
+        .map(|f| f.normal())
+        .find(|f| *f != Vec2::ZERO)?;
+    Some(if pair.collider1() == entity {
+        normal
+    } else {
+        -normal
+    })
 }
@@ -0,0 +1,188 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::prelude::Velocity;
+use serde_json::json;
+
+use crate::{
+    c_chassis::Chassis,
+    c_event::{CTEvent, EventSink},
+    c_health::Health,
+    c_objective::Objective,
+    c_pickup::PowerUps,
+    c_radar_needs_update::RadarNeedsUpdate,
+    c_tank::{AllTankInfo, DamageDealer, Radar, Tank, TankId},
+    game_mode::{ActiveGameMode, ModeTank},
+    map::Map,
+    observer::TankDestroyed,
+    rules::Rules,
+    s_safe_zone::SafeZone,
+    Game, TickState,
+};
+
+pub fn setup_game_mode(mut commands: Commands, rules: Res<Rules>, tank_state: Res<AllTankInfo>) {
+    let mode = rules.mode.create(&rules, tank_state.all.len());
+
+    for objective in mode.objectives() {
+        commands.spawn((
+            SpatialBundle {
+                transform: Transform::from_xyz(objective.x, objective.y, 1.0),
+                visibility: Visibility::Visible,
+                ..default()
+            },
+            objective,
+        ));
+    }
+
+    commands.insert_resource(ActiveGameMode(mode));
+}
+
+/// Snapshot of every tank by index for `GameMode`, see `ModeTank`.
+pub fn mode_tanks<'a>(
+    n_tanks: usize,
+    tanks: impl Iterator<Item = (&'a TankId, &'a Transform, &'a Health, &'a DamageDealer)>,
+) -> Vec<ModeTank> {
+    let mut mode_tanks = (0..n_tanks)
+        .map(|index| ModeTank {
+            index,
+            ..default()
+        })
+        .collect::<Vec<ModeTank>>();
+
+    for (tank_id, transform, health, damage_dealer) in tanks {
+        mode_tanks[tank_id.0] = ModeTank {
+            index: tank_id.0,
+            alive: health.val > 0,
+            position: transform.translation.truncate(),
+            damage_dealt: damage_dealer.damage_dealt,
+        };
+    }
+    mode_tanks
+}
+
+pub fn update_game_mode(
+    mut commands: Commands,
+    state: Res<TickState>,
+    tank_state: Res<AllTankInfo>,
+    rules: Res<Rules>,
+    map: Res<Map>,
+    zone: Res<SafeZone>,
+    mut mode: ResMut<ActiveGameMode>,
+    mut tanks: Query<
+        (
+            &TankId,
+            &mut Tank,
+            &mut Transform,
+            &mut Health,
+            &DamageDealer,
+            &mut Velocity,
+            &mut PowerUps,
+            &Chassis,
+        ),
+        With<Tank>,
+    >,
+    mut radars: Query<&mut Radar>,
+    mut event_sinks: Query<(&TankId, &mut EventSink)>,
+    mut objectives: Query<(&mut Objective, &mut Transform), Without<Tank>>,
+    mut tanks_destroyed: EventReader<TankDestroyed>,
+) {
+    for destroyed in tanks_destroyed.iter() {
+        mode.0
+            .on_tank_destroyed(state.count, destroyed.tank_id.0, destroyed.by.map(|f| f.0));
+    }
+
+    let mut events = vec![];
+
+    let respawns = mode.0.respawns(state.count);
+    if !respawns.is_empty() {
+        let mut others = tanks
+            .iter()
+            .filter(|f| f.3.val > 0)
+            .map(|f| f.2.translation.truncate())
+            .collect::<Vec<Vec2>>();
+
+        for (
+            tank_id,
+            mut tank,
+            mut transform,
+            mut health,
+            _,
+            mut velocity,
+            mut power_ups,
+            chassis,
+        ) in &mut tanks
+        {
+            if !respawns.contains(&tank_id.0) {
+                continue;
+            }
+
+            let position = Game::safe_spawn_position(&others, &map, &zone);
+            others.push(position);
+
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            transform.rotation = Quat::from_rotation_z(Tank::INITIAL_ROTATION);
+            *velocity = Velocity::zero();
+            tank.last_velocity = Vec2::ZERO;
+            tank.heat = 0;
+            tank.rejected_since_last_shot = false;
+            health.val = chassis.max_health();
+            power_ups.rapid_fire = 0;
+            power_ups.shield = rules.respawn_invulnerability;
+
+            radars.get_mut(tank.radar).unwrap().disabled = false;
+            commands.entity(tank.radar).insert(RadarNeedsUpdate);
+
+            events.push((
+                tank_id.0,
+                CTEvent {
+                    event_type: "respawned".to_string(),
+                    info: json!({
+                        "x": position.x,
+                        "y": position.y,
+                        "invulnerable": rules.respawn_invulnerability,
+                    }),
+                },
+            ));
+        }
+    }
+
+    let mode_tanks = mode_tanks(
+        tank_state.all.len(),
+        tanks.iter().map(|f| (f.0, f.2, f.3, f.4)),
+    );
+
+    // tell everyone what they are playing for at the start of the match
+    if state.count == 1 {
+        for tank in &mode_tanks {
+            events.push((
+                tank.index,
+                CTEvent {
+                    event_type: "game_mode".to_string(),
+                    info: json!({
+                        "mode": mode.0.kind(),
+                        "objectives": mode.0.objectives(),
+                    }),
+                },
+            ));
+        }
+    }
+
+    mode.0.update(state.count, &mode_tanks, &mut events);
+
+    let mut event_sinks = event_sinks
+        .iter_mut()
+        .map(|(tank_id, event_sink)| (tank_id.0, event_sink))
+        .collect::<HashMap<usize, Mut<EventSink>>>();
+    for (index, event) in events {
+        if let Some(event_sink) = event_sinks.get_mut(&index) {
+            event_sink.queue.push(event);
+        }
+    }
+
+    let next_objectives = mode.0.objectives();
+    for (mut objective, mut transform) in &mut objectives {
+        let next_objective = next_objectives[objective.index].clone();
+        transform.translation.x = next_objective.x;
+        transform.translation.y = next_objective.y;
+        *objective = next_objective;
+    }
+}
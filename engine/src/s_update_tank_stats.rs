@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use crate::{
+    c_health::Health,
+    c_tank::TankId,
+    c_tank_stats::TankStats,
+    observer::{RadarScanned, ShotFired, TankHit},
+    rules::Rules,
+    s_wrap_arena::wrapped_delta,
+    CollisionType, TickState,
+};
+
+pub fn update_tank_stats(
+    state: Res<TickState>,
+    rules: Res<Rules>,
+    mut shots_fired: EventReader<ShotFired>,
+    mut tanks_hit: EventReader<TankHit>,
+    mut radars_scanned: EventReader<RadarScanned>,
+    mut query: Query<(&TankId, &mut TankStats, &Transform, &Health)>,
+) {
+    let shots_fired = shots_fired.iter().collect::<Vec<&ShotFired>>();
+    let tanks_hit = tanks_hit.iter().collect::<Vec<&TankHit>>();
+    let radars_scanned = radars_scanned.iter().collect::<Vec<&RadarScanned>>();
+
+    for (tank_id, mut stats, transform, health) in &mut query {
+        stats.shots_fired += shots_fired.iter().filter(|f| f.tank_id == *tank_id).count() as u32;
+        stats.radar_scans += radars_scanned.iter().filter(|f| f.tank_id == *tank_id).count() as u32;
+
+        for hit in &tanks_hit {
+            let own_bullet_hit = hit.collision_type == CollisionType::Bullet
+                && hit.by_tank == Some(*tank_id)
+                && hit.tank_id != *tank_id;
+            if own_bullet_hit && hit.shielded {
+                stats.blocked += 1;
+            } else if own_bullet_hit {
+                stats.hits += 1;
+                if stats.time_to_first_hit.is_none() {
+                    stats.time_to_first_hit = Some(state.count);
+                }
+            }
+
+            if hit.tank_id != *tank_id {
+                continue;
+            }
+
+            stats.damage_taken += hit.damage;
+            if let Some(by_tank) = hit.by_tank {
+                *stats.damage_taken_by_source.entry(by_tank.0).or_insert(0) += hit.damage;
+            }
+            match hit.collision_type {
+                CollisionType::Bullet => stats.bullet_damage_taken += hit.damage,
+                CollisionType::Tank => stats.ram_damage_taken += hit.damage,
+                CollisionType::Wall | CollisionType::Obstacle => {
+                    stats.wall_damage_taken += hit.damage
+                }
+                CollisionType::Radar | CollisionType::Pickup => {}
+            }
+        }
+
+        if health.val > 0 {
+            let position = transform.translation.truncate();
+            if let Some(last_position) = stats.last_position {
+                // don't count the teleport across the arena when a tank wraps around
+                stats.distance_travelled += if rules.wrap_around {
+                    wrapped_delta(last_position, position).length()
+                } else {
+                    position.distance(last_position)
+                };
+            }
+            stats.last_position = Some(position);
+            stats.ticks_alive += 1;
+        } else {
+            // respawned tanks don't count the jump to their spawn point
+            stats.last_position = None;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    c_health::Health,
+    c_tank::Tank,
+    c_wreck::{Wreck, WreckMode},
+    rules::Rules,
+    tank_collision_groups, CCollider, CollisionMask, CollisionType,
+};
+
+/// Converts destroyed tanks into wrecks and turns them back into tanks when they respawn.
+pub fn update_wrecks(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    query: Query<(Entity, &Health, Option<&Wreck>), With<Tank>>,
+) {
+    if rules.wreck == WreckMode::Remain {
+        return;
+    }
+
+    for (entity, health, wreck) in &query {
+        match (health.val > 0, wreck.is_some()) {
+            (false, false) => {
+                let mut tank = commands.entity(entity);
+                tank.insert((Wreck, RigidBody::Fixed, Velocity::zero()));
+
+                if rules.wreck == WreckMode::Static {
+                    // blocks the same things obstacles do
+                    tank.insert((
+                        CCollider {
+                            collision_type: CollisionType::Obstacle,
+                        },
+                        CollisionGroups::new(
+                            Group::from_bits_truncate(CollisionMask::WALL),
+                            Group::from_bits_truncate(
+                                CollisionMask::TANK | CollisionMask::BULLET | CollisionMask::RADAR,
+                            ),
+                        ),
+                    ));
+                } else {
+                    tank.insert(CollisionGroups::new(
+                        Group::from_bits_truncate(CollisionMask::NONE),
+                        Group::from_bits_truncate(CollisionMask::NONE),
+                    ));
+                }
+            }
+            (true, true) => {
+                commands.entity(entity).remove::<Wreck>().insert((
+                    RigidBody::Dynamic,
+                    CCollider {
+                        collision_type: CollisionType::Tank,
+                    },
+                    tank_collision_groups(),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
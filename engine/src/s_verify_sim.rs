@@ -0,0 +1,286 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    io,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{app::AppExit, prelude::*};
+
+use ct_api::{Command, Commands};
+
+use crate::{
+    c_chassis::Chassis,
+    c_client::{Client, ReaderClient},
+    c_tank::{AllTankInfo, Gun, Radar, Tank, TankId, TankInfo},
+    core_plugin::CoreCTPlugin,
+    create_base_tank, create_gun, create_radar,
+    map::Map,
+    replay::{results_chassis, z_rotation, LegacySim, ReplayReader},
+    rules::Rules,
+    s_apply_commands::apply_commands,
+    s_request_commands::request_commands,
+    s_setup_walls::setup_walls,
+    Game, TickState,
+};
+
+/// The first tick at which a re-simulation stopped matching the recording.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub tick: u32,
+    pub expected_hash: u64,
+    pub actual_hash: u64,
+    /// tanks whose transforms differ, by index
+    pub tanks: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    pub ticks_recorded: usize,
+    pub ticks_checked: usize,
+    pub divergence: Option<Divergence>,
+}
+
+impl VerifyReport {
+    pub fn is_deterministic(&self) -> bool {
+        self.divergence.is_none() && self.ticks_checked == self.ticks_recorded
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.divergence {
+            Some(divergence) => write!(
+                f,
+                "diverged at tick {} (expected {:016x}, got {:016x}) for tanks {:?}",
+                divergence.tick, divergence.expected_hash, divergence.actual_hash, divergence.tanks
+            ),
+            None => write!(
+                f,
+                "matched {}/{} ticks",
+                self.ticks_checked, self.ticks_recorded
+            ),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct VerifySim {
+    /// commands[tank][tick]
+    commands: Vec<Vec<Command>>,
+    /// states[tank][tick] as laid out by `tank_state`, empty while a tank is gone
+    states: Vec<Vec<Vec<f32>>>,
+    chassis: Vec<Chassis>,
+    /// replays store rotations as angles, `sim.txt` as quaternions
+    angles: bool,
+    // shared with `verify_sim`, since running the app consumes its world
+    report: Arc<Mutex<VerifyReport>>,
+}
+
+impl VerifySim {
+    fn n_ticks(&self) -> usize {
+        self.commands.iter().map(|f| f.len()).min().unwrap_or(0)
+    }
+
+    /// A tank's recorded state: `x, y, rotation, radar rotation, gun rotation` with each
+    /// rotation either as an angle or as `qz, qw`.
+    fn tank_state(&self, transform: &Transform, radar: &Transform, gun: &Transform) -> Vec<f32> {
+        let rotations = [transform.rotation, radar.rotation, gun.rotation];
+        let mut state = vec![transform.translation.x, transform.translation.y];
+        for rotation in rotations {
+            if self.angles {
+                state.push(z_rotation(rotation.z, rotation.w));
+            } else {
+                state.extend([rotation.z, rotation.w]);
+            }
+        }
+        state
+    }
+}
+
+/// Hashes the bits of every tank's `x, y, qz, qw, radar qz, radar qw, gun qz, gun qw`,
+/// ordered by tank index, in the same layout `save_commands` writes to `sim.txt`.
+pub fn state_hash(transforms: &[&[f32]]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for transform in transforms {
+        for val in transform.iter() {
+            val.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+pub fn setup_verify_tanks(
+    state: Res<AllTankInfo>,
+    verify: Res<VerifySim>,
+    map: Res<Map>,
+    mut commands: Commands,
+) {
+    for tank_info in state.all.iter() {
+        let Vec2 { x, y } = Game::spawn_position(tank_info.index, state.all.len(), &map);
+
+        let gun = create_gun(&mut commands, x, y);
+        let radar = create_radar(&mut commands, x, y);
+
+        let client = Client {
+            client: Box::new(ReaderClient {
+                lines: verify.commands[tank_info.index].to_vec(),
+            }),
+        };
+        create_base_tank(
+            tank_info,
+            &mut commands,
+            gun,
+            radar,
+            x,
+            y,
+            client,
+            verify.chassis[tank_info.index],
+        );
+    }
+}
+
+/// Compares the live state with the recorded one at the point `save_commands` records it.
+pub fn check_sim_state(
+    state: Res<TickState>,
+    verify: Res<VerifySim>,
+    mut exit: EventWriter<AppExit>,
+    tanks: Query<(&TankId, &Transform, &Tank)>,
+    radars: Query<&Transform, With<Radar>>,
+    guns: Query<&Transform, With<Gun>>,
+) {
+    let tick = state.count as usize;
+    if tick >= verify.n_ticks() {
+        exit.send(AppExit);
+        return;
+    }
+
+    let mut actual = vec![vec![]; verify.states.len()];
+    for (tank_id, transform, tank) in &tanks {
+        let radar = radars.get(tank.radar).unwrap();
+        let gun = guns.get(tank.gun).unwrap();
+        actual[tank_id.0] = verify.tank_state(transform, radar, gun);
+    }
+    let expected = verify
+        .states
+        .iter()
+        .map(|f| f[tick].as_slice())
+        .collect::<Vec<&[f32]>>();
+
+    let expected_hash = state_hash(&expected);
+    let actual_hash = state_hash(&actual.iter().map(|f| f.as_slice()).collect::<Vec<&[f32]>>());
+
+    if expected_hash != actual_hash {
+        let tanks = (0..actual.len())
+            .filter(|&i| expected[i] != actual[i].as_slice())
+            .collect();
+        verify.report.lock().unwrap().divergence = Some(Divergence {
+            tick: state.count,
+            expected_hash,
+            actual_hash,
+            tanks,
+        });
+        exit.send(AppExit);
+        return;
+    }
+
+    verify.report.lock().unwrap().ticks_checked += 1;
+}
+
+/// Re-runs the commands of a `sim.txt` log headlessly and compares the per-tick state hash
+/// against the recorded transforms, stopping at the first divergent tick. The log doesn't
+/// say which rules or map the match had, so it is played with the defaults.
+pub fn verify_sim(legacy: &str) -> VerifyReport {
+    let sim = LegacySim::parse(legacy);
+    let chassis = sim.chassis();
+    let tank_infos = sim.tank_infos();
+
+    run_verify(
+        tank_infos,
+        Rules::default(),
+        Map::default(),
+        sim.commands,
+        sim.transforms,
+        chassis,
+        false,
+    )
+}
+
+/// Like `verify_sim`, for a replay. The match is played with the rules and map of the
+/// replay header.
+pub fn verify_replay(replay: &[u8]) -> Result<VerifyReport, io::Error> {
+    let (header, frames, results) = ReplayReader::new(replay)?.read_all()?;
+    let invalid_header = |err| io::Error::new(io::ErrorKind::InvalidData, err);
+    let rules = Rules::from_header(&header.rules).map_err(invalid_header)?;
+    let map = Map::from_header(&header.map).map_err(invalid_header)?;
+    let n_tanks = header.tanks.len();
+
+    let mut commands = vec![vec![]; n_tanks];
+    let mut states = vec![vec![]; n_tanks];
+    for frame in &frames {
+        for index in 0..n_tanks {
+            let tank = frame.tanks.iter().find(|f| f.index == index);
+            commands[index].push(tank.map_or(Commands::NONE, |f| f.commands));
+            states[index].push(tank.map_or(vec![], |f| {
+                vec![f.x, f.y, f.rotation, f.radar_rotation, f.gun_rotation]
+            }));
+        }
+    }
+    let chassis = header
+        .tanks
+        .iter()
+        .map(|f| results_chassis(results.as_ref(), &f.container_name))
+        .collect();
+
+    Ok(run_verify(
+        header.tanks,
+        rules,
+        map,
+        commands,
+        states,
+        chassis,
+        true,
+    ))
+}
+
+fn run_verify(
+    tank_infos: Vec<TankInfo>,
+    rules: Rules,
+    map: Map,
+    commands: Vec<Vec<Command>>,
+    states: Vec<Vec<Vec<f32>>>,
+    chassis: Vec<Chassis>,
+    angles: bool,
+) -> VerifyReport {
+    let verify = VerifySim {
+        commands,
+        states,
+        chassis,
+        angles,
+        report: Arc::new(Mutex::new(VerifyReport {
+            ticks_recorded: 0,
+            ticks_checked: 0,
+            divergence: None,
+        })),
+    };
+    verify.report.lock().unwrap().ticks_recorded = verify.n_ticks();
+    let report = verify.report.clone();
+
+    App::new()
+        .add_plugins(MinimalPlugins)
+        .insert_resource(AllTankInfo { all: tank_infos })
+        .insert_resource(rules)
+        .insert_resource(map)
+        .insert_resource(verify)
+        .add_systems(Startup, (setup_walls, setup_verify_tanks).chain())
+        .add_plugins(CoreCTPlugin)
+        .add_systems(
+            Update,
+            check_sim_state.after(request_commands).before(apply_commands),
+        )
+        .run();
+
+    let report = report.lock().unwrap().clone();
+    report
+}
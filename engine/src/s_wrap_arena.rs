@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    c_radar_ghost::RadarGhost,
+    c_tank::{Bullet, Tank},
+    radar_collider, radar_collision_groups,
+    rules::Rules,
+    CCollider, CollisionType, Game,
+};
+
+/// Moves tanks and bullets that left the arena during the last physics step to the
+/// opposite edge, and gives new radars their ghosts.
+pub fn wrap_arena(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    mut query: Query<&mut Transform, Or<(With<Tank>, With<Bullet>)>>,
+    query_tank: Query<(Entity, &Tank), Added<Tank>>,
+) {
+    if !rules.wrap_around {
+        return;
+    }
+
+    for mut transform in &mut query {
+        let wrapped = wrap_position(transform.translation.truncate());
+        if wrapped != transform.translation.truncate() {
+            transform.translation.x = wrapped.x;
+            transform.translation.y = wrapped.y;
+        }
+    }
+
+    for (tank_entity, tank) in &query_tank {
+        for offset in RadarGhost::offsets(Game::WIDTH, Game::HEIGHT) {
+            commands.spawn((
+                RadarGhost {
+                    radar: tank.radar,
+                    tank: tank_entity,
+                    offset,
+                },
+                CCollider {
+                    collision_type: CollisionType::Radar,
+                },
+                TransformBundle::default(),
+                Sensor,
+                RigidBody::KinematicVelocityBased,
+                radar_collider(),
+                radar_collision_groups(),
+                Velocity::zero(),
+            ));
+        }
+    }
+}
+
+/// Puts the ghosts where their radar is about to be swept to during the next physics step.
+pub fn sync_radar_ghosts(
+    rules: Res<Rules>,
+    query_radar: Query<(&Transform, &Velocity), Without<RadarGhost>>,
+    mut query_ghost: Query<(&RadarGhost, &mut Transform, &mut Velocity)>,
+) {
+    if !rules.wrap_around {
+        return;
+    }
+
+    for (ghost, mut transform, mut velocity) in &mut query_ghost {
+        let Ok((radar_transform, radar_velocity)) = query_radar.get(ghost.radar) else {
+            continue;
+        };
+        *transform = *radar_transform;
+        transform.translation += ghost.offset.extend(0.);
+        *velocity = *radar_velocity;
+    }
+}
+
+/// The same point inside the arena, `x` in `[-WIDTH / 2, WIDTH / 2)` and likewise for `y`.
+/// Positions already inside are returned unchanged.
+pub fn wrap_position(position: Vec2) -> Vec2 {
+    let wrap = |val: f32, size: f32| {
+        if val < -size / 2. {
+            val + size
+        } else if val >= size / 2. {
+            val - size
+        } else {
+            val
+        }
+    };
+    Vec2::new(wrap(position.x, Game::WIDTH), wrap(position.y, Game::HEIGHT))
+}
+
+/// The shortest way from `from` to `to` in a wrapping arena, which may cross an edge.
+pub fn wrapped_delta(from: Vec2, to: Vec2) -> Vec2 {
+    wrap_position(to - from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_inside_are_unchanged() {
+        for position in [Vec2::ZERO, Vec2::new(100., -200.), Vec2::new(-512., -320.)] {
+            assert_eq!(wrap_position(position), position);
+        }
+    }
+
+    #[test]
+    fn positions_outside_come_back_on_the_opposite_edge() {
+        assert_eq!(wrap_position(Vec2::new(520., 0.)), Vec2::new(-504., 0.));
+        assert_eq!(wrap_position(Vec2::new(-515., 0.)), Vec2::new(509., 0.));
+        assert_eq!(wrap_position(Vec2::new(0., 330.)), Vec2::new(0., -310.));
+        assert_eq!(wrap_position(Vec2::new(0., -325.)), Vec2::new(0., 315.));
+        assert_eq!(
+            wrap_position(Vec2::new(512., 320.)),
+            Vec2::new(-512., -320.)
+        );
+    }
+
+    #[test]
+    fn deltas_take_the_short_way_across_the_seam() {
+        let delta = wrapped_delta(Vec2::new(510., 0.), Vec2::new(-510., 0.));
+        assert_eq!(delta, Vec2::new(4., 0.));
+
+        let delta = wrapped_delta(Vec2::new(0., -318.), Vec2::new(0., 318.));
+        assert_eq!(delta, Vec2::new(0., -4.));
+
+        let delta = wrapped_delta(Vec2::new(-10., 5.), Vec2::new(10., -5.));
+        assert_eq!(delta, Vec2::new(20., -10.));
+    }
+}
@@ -14,8 +14,12 @@ use bevy::{
 // };
 use c_nametag::NameTag;
 use c_tracks::Tracks;
+use ctengine::c_chassis::Chassis;
 use ctengine::c_tank::TankInfo;
 use ctengine::create_base_tank;
+use ctengine::s_safe_zone::SafeZone;
+use ctengine::map::Map;
+use ctengine::rules::Rules;
 use ctengine::{
     c_tank::Tank, s_apply_commands::apply_commands, s_request_commands::request_commands,
 };
@@ -27,23 +31,43 @@ use s_update_nametag::update_nametag;
 pub mod c_healthbar;
 pub mod c_nametag;
 pub mod c_particle;
+pub mod c_playback_text;
 pub mod c_tracks;
+pub mod s_spawn_terrain;
 pub mod s_spawn_tracks;
 pub mod s_update_tracks;
-use ctengine::{create_gun, create_radar, Game};
-use s_on_added_bullet::{on_added_bullet, COLORS};
+use ctengine::{
+    create_gun, create_radar, CustomAsset, CustomAssetLoader, CustomAssetState, Game, ReplayAsset,
+    ReplayAssetLoader, ReplayAssetState,
+};
+use s_replay_playback::{
+    advance_replay_playback, apply_replay_frame, replay_playback_controls,
+    setup_replay_playback, update_playback_text, update_replay_safe_zone, ReplayPlayback,
+};
+use s_on_added_bullet::{on_added_bullet, tank_color};
+use s_on_added_objective::on_added_objective;
+use s_on_added_obstacle::on_added_obstacle;
+use s_on_added_pickup::on_added_pickup;
+use s_update_safe_zone::update_safe_zone_boundary;
+use s_spawn_terrain::spawn_terrain;
 use s_update_radar::update_radar;
 use s_update_tank::update_tank;
+use s_update_bullet::update_bullet;
 use s_update_tracks::update_tracks;
 // use s_update_tracks::update_tracks;
 pub mod s_on_added_bullet;
+pub mod s_on_added_objective;
+pub mod s_on_added_obstacle;
+pub mod s_on_added_pickup;
 pub mod s_setup_graphics;
 pub mod s_setup_ground;
 pub mod s_update_healthbar;
 pub mod s_update_nametag;
 pub mod s_update_radar;
+pub mod s_update_safe_zone;
 pub mod s_update_tank;
-pub mod s_setup_reader_tanks;
+pub mod s_update_bullet;
+pub mod s_replay_playback;
 
 
 // use crate::s_update_healthbar::update_healthbar;
@@ -70,6 +94,19 @@ const TANK_BARREL_IMAGES: &[&str] = &[
     "tankDark_barrel1.png",
 ];
 
+// light sprites tinted with `tank_color` once the coloured ones run out
+const TINTED_TANK_BODY_IMAGE: &str = "tankBody_sand.png";
+const TINTED_TANK_BARREL_IMAGE: &str = "tankSand_barrel1.png";
+
+/// Sprite colour of a living tank.
+pub fn tank_tint(index: usize) -> Color {
+    if index < TANK_BODY_IMAGES.len() {
+        Color::WHITE
+    } else {
+        tank_color(index)
+    }
+}
+
 // pub fn create_environment(commands: &mut Commands, asset_server: &Res<AssetServer>) {
 //     for x in 0..(Game::WIDTH as i32 / 64) {
 //         for y in 0..(Game::HEIGHT as i32 / 64) {
@@ -93,13 +130,15 @@ const TANK_BARREL_IMAGES: &[&str] = &[
 pub fn create_graphics_tank(
     commands: &mut Commands,
     tank_info: &TankInfo,
+    n_tanks: usize,
+    map: &Map,
     client: impl Component,
+    chassis: Chassis,
     asset_server: &Res<AssetServer>,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
 ) -> Entity {
-    let x = 150.0 * (tank_info.index as f32) + 10.0;
-    let y = 0.0;
+    let Vec2 { x, y } = Game::spawn_position(tank_info.index, n_tanks, map);
 
     let gun = create_gun(commands, x, y);
     let mut gun = commands.entity(gun);
@@ -109,14 +148,19 @@ pub fn create_graphics_tank(
     // let mut t2 = t.clone();
     gun.insert(SpriteBundle {
         transform: {
-            let mut j = t;
+            let mut j = t.with_scale(Vec3::splat(chassis.scale()));
             j.translation.z = 2.1;
             j
         },
-        texture: asset_server.load(TANK_BARREL_IMAGES[tank_info.index % TANK_BARREL_IMAGES.len()]),
+        texture: asset_server.load(
+            *TANK_BARREL_IMAGES
+                .get(tank_info.index)
+                .unwrap_or(&TINTED_TANK_BARREL_IMAGE),
+        ),
         sprite: Sprite {
             anchor: Anchor::Custom(Vec2::new(0.0, -0.35)),
             flip_y: true,
+            color: tank_tint(tank_info.index),
             ..default()
         },
         ..default()
@@ -149,16 +193,17 @@ pub fn create_graphics_tank(
     radar.insert(MaterialMesh2dBundle {
         // mesh: meshes.add(shape::Circle::new(50.).into()).into(),
         mesh: meshes.add(mesh).into(),
-        material: materials.add(ColorMaterial::from(COLORS[tank_info.index % COLORS.len()].with_a(0.3))),
+        material: materials.add(ColorMaterial::from(tank_color(tank_info.index).with_a(0.3))),
         transform: t.with_translation(Vec3 { x: t.translation.x, y: t.translation.y, z: 3. }),
         ..default()
     });
     let radar = radar.id();
 
-    let mut k = Transform::from_rotation(Quat::from_rotation_z(0.0));
+    let mut k = Transform::from_rotation(Quat::from_rotation_z(0.0))
+        .with_scale(Vec3::splat(chassis.scale()));
     k.translation.z = 1.;
 
-    let tank = create_base_tank(tank_info, commands, gun, radar, x, y, client);
+    let tank = create_base_tank(tank_info, commands, gun, radar, x, y, client, chassis);
 
     let tank = commands
         .entity(tank)
@@ -169,8 +214,15 @@ pub fn create_graphics_tank(
         .with_children(|parent| {
             parent.spawn(SpriteBundle {
                 transform: k,
-                texture: asset_server
-                    .load(TANK_BODY_IMAGES[tank_info.index % TANK_BODY_IMAGES.len()]),
+                texture: asset_server.load(
+                    *TANK_BODY_IMAGES
+                        .get(tank_info.index)
+                        .unwrap_or(&TINTED_TANK_BODY_IMAGE),
+                ),
+                sprite: Sprite {
+                    color: tank_tint(tank_info.index),
+                    ..default()
+                },
                 ..default()
             });
         })
@@ -224,7 +276,7 @@ pub fn create_graphics_tank(
             // We align text to the top-left, so this transform is the top-left corner of our text. The
             // box is centered at box_position, so it is necessary to move by half of the box size to
             // keep the text in the box.
-            transform: Transform::from_xyz(x, y - Tank::RADIUS - 10.0, 4.0),
+            transform: Transform::from_xyz(x, y - chassis.radius() - 10.0, 4.0),
             ..default()
         },
         NameTag { tank },
@@ -251,6 +303,9 @@ impl Plugin for CoreCTGraphicsPlugin {
                 is_on: false,
                 index: 0,
             })
+            .init_resource::<SafeZone>()
+            .init_resource::<Map>()
+            .init_resource::<Rules>()
             // .insert_resource( Window {
             //         title: "Code Tanks".to_string(),
             //         width: Game::WIDTH,
@@ -284,11 +339,17 @@ impl Plugin for CoreCTGraphicsPlugin {
                 (
                     update_nametag,
                     on_added_bullet,
+                    on_added_pickup,
+                    on_added_objective,
+                    on_added_obstacle,
                     spawn_tracks,
                     update_tracks,
                     update_healthbar,
                     update_radar,
                     update_tank,
+                    update_bullet,
+                    update_safe_zone_boundary,
+                    spawn_terrain,
                 ), // "on_added_bullet",
                    // SystemStage::single_threaded().with_system(on_added_bullet),
             );
@@ -307,6 +368,34 @@ impl Plugin for CoreCTGraphicsPlugin {
     }
 }
 
+/// Plays back a recorded match instead of simulating one. Load the replay by setting
+/// `ReplayAssetState` (`.ctr`) or `CustomAssetState` (legacy `sim.txt`) in a startup system.
+pub struct ReplayPlaybackPlugin;
+
+impl Plugin for ReplayPlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayPlayback>()
+            .init_resource::<ReplayAssetState>()
+            .init_resource::<CustomAssetState>()
+            .add_asset::<ReplayAsset>()
+            .init_asset_loader::<ReplayAssetLoader>()
+            .add_asset::<CustomAsset>()
+            .init_asset_loader::<CustomAssetLoader>()
+            .add_systems(
+                Update,
+                (
+                    setup_replay_playback,
+                    replay_playback_controls,
+                    advance_replay_playback,
+                    apply_replay_frame,
+                    update_replay_safe_zone,
+                    update_playback_text,
+                )
+                    .chain(),
+            );
+    }
+}
+
 // #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
 // pub struct UpdateHealthbar;
 
@@ -5,6 +5,14 @@ use ctengine::c_tank::{Bullet, Tank};
 pub const COLORS: &[&Color] = &[&Color::RED, &Color::GREEN, &Color::BLUE, &Color::GRAY];
 pub const DISABLED_COLOR: Color = Color::GRAY;
 
+/// Colour of tank `index`: the four sprite colours first, then hues spread by the golden angle.
+pub fn tank_color(index: usize) -> Color {
+    match COLORS.get(index) {
+        Some(color) => **color,
+        None => Color::hsl((index as f32 * 137.508) % 360.0, 0.75, 0.5),
+    }
+}
+
 pub fn on_added_bullet(
     mut commands: Commands,
     query_tank: Query<&Tank>,
@@ -34,13 +42,13 @@ pub fn on_added_bullet(
             // ));
             parent.spawn(MaterialMesh2dBundle {
                 mesh: meshes.add(shape::Circle::new(Bullet::RADIUS).into()).into(),
-                material: materials.add(ColorMaterial::from(
-                    *COLORS[query_tank
+                material: materials.add(ColorMaterial::from(tank_color(
+                    query_tank
                         .get(query_bullet.get(e).unwrap().tank)
                         .unwrap()
-                        .info.index
-                        % COLORS.len()],
-                )),
+                        .info
+                        .index,
+                ))),
                 ..default()
             });
         });
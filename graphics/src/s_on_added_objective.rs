@@ -0,0 +1,27 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use ctengine::c_objective::{Objective, ObjectiveKind};
+
+use crate::s_on_added_bullet::tank_color;
+
+pub fn on_added_objective(
+    mut commands: Commands,
+    query: Query<(Entity, &Objective), Added<Objective>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (e, objective) in query.iter() {
+        let color = match (objective.kind, objective.team) {
+            (ObjectiveKind::Hill, _) => Color::GOLD.with_a(0.3),
+            (ObjectiveKind::Flag, Some(team)) => tank_color(team),
+            (ObjectiveKind::Flag, None) => Color::WHITE,
+        };
+
+        commands.entity(e).with_children(|parent| {
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: meshes.add(shape::Circle::new(objective.radius).into()).into(),
+                material: materials.add(ColorMaterial::from(color)),
+                ..default()
+            });
+        });
+    }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+use ctengine::c_obstacle::Obstacle;
+
+pub fn obstacle_color(obstacle: &Obstacle) -> Color {
+    if obstacle.health.is_some() {
+        Color::rgb(0.55, 0.4, 0.25)
+    } else {
+        Color::DARK_GRAY
+    }
+}
+
+pub fn on_added_obstacle(mut commands: Commands, query: Query<(Entity, &Obstacle), Added<Obstacle>>) {
+    for (e, obstacle) in query.iter() {
+        commands.entity(e).with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: obstacle_color(obstacle),
+                    custom_size: Some(Vec2::new(obstacle.width, obstacle.height)),
+                    ..default()
+                },
+                ..default()
+            });
+        });
+    }
+}
@@ -0,0 +1,27 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use ctengine::c_pickup::Pickup;
+
+pub fn pickup_color(pickup: &Pickup) -> Color {
+    match pickup {
+        Pickup::Repair => Color::LIME_GREEN,
+        Pickup::RapidFire => Color::ORANGE,
+        Pickup::Shield => Color::CYAN,
+    }
+}
+
+pub fn on_added_pickup(
+    mut commands: Commands,
+    query: Query<(Entity, &Pickup), Added<Pickup>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (e, pickup) in query.iter() {
+        commands.entity(e).with_children(|parent| {
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: meshes.add(shape::Circle::new(Pickup::RADIUS).into()).into(),
+                material: materials.add(ColorMaterial::from(pickup_color(pickup))),
+                ..default()
+            });
+        });
+    }
+}
@@ -0,0 +1,368 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    sprite::ColorMaterial,
+    text::{Text, Text2dBundle, TextAlignment, TextStyle},
+    utils::HashMap,
+};
+use ctengine::{
+    c_health::Health,
+    c_obstacle::Obstacle,
+    c_radar_needs_update::RadarNeedsUpdate,
+    c_tank::{Bullet, Radar, Tank},
+    map::Map,
+    replay::{convert_legacy, results_chassis, Frame, ReplayReader},
+    rules::Rules,
+    s_safe_zone::SafeZone,
+    CustomAsset, CustomAssetState, Game, ReplayAsset, ReplayAssetState,
+};
+
+use crate::{c_playback_text::PlaybackText, create_graphics_tank};
+
+#[derive(Component)]
+pub struct ReplayedTank;
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub frames: Vec<Frame>,
+    pub tick: usize,
+    pub paused: bool,
+    pub speed: f32,
+    pub loaded: bool,
+    /// rules of the recorded match, for state derived from the tick such as the safe zone
+    pub rules: Rules,
+    pub map: Map,
+    progress: f32,
+    applied: Option<usize>,
+    tanks: Vec<Entity>,
+    bullets: HashMap<u32, Entity>,
+    obstacles: HashMap<usize, Entity>,
+}
+
+impl Default for ReplayPlayback {
+    fn default() -> Self {
+        ReplayPlayback {
+            frames: vec![],
+            tick: 0,
+            paused: false,
+            speed: 1.0,
+            loaded: false,
+            rules: Rules::default(),
+            map: Map::default(),
+            progress: 0.0,
+            applied: None,
+            tanks: vec![],
+            bullets: HashMap::default(),
+            obstacles: HashMap::default(),
+        }
+    }
+}
+
+impl ReplayPlayback {
+    pub const MIN_SPEED: f32 = 0.125;
+    pub const MAX_SPEED: f32 = 8.0;
+    pub const SEEK_TICKS: usize = 60;
+
+    pub fn last_tick(&self) -> usize {
+        self.frames.len().saturating_sub(1)
+    }
+
+    pub fn seek(&mut self, tick: usize) {
+        self.tick = tick.min(self.last_tick());
+        self.progress = 0.0;
+    }
+
+    pub fn step_forward(&mut self, ticks: usize) {
+        self.seek(self.tick + ticks);
+    }
+
+    pub fn step_back(&mut self, ticks: usize) {
+        self.seek(self.tick.saturating_sub(ticks));
+    }
+}
+
+pub fn setup_replay_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    replay_state: Res<ReplayAssetState>,
+    legacy_state: Res<CustomAssetState>,
+    replay_assets: Res<Assets<ReplayAsset>>,
+    legacy_assets: Res<Assets<CustomAsset>>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if playback.loaded {
+        return;
+    }
+
+    let (header, frames, results) = if let Some(replay) = replay_assets.get(&replay_state.handle) {
+        ReplayReader::new(&replay.0[..])
+            .and_then(|f| f.read_all())
+            .expect("invalid replay file")
+    } else if matches!(
+        asset_server.get_load_state(&replay_state.handle),
+        LoadState::Failed | LoadState::NotLoaded
+    ) {
+        // fall back to re-building frames from a legacy sim.txt
+        match legacy_assets.get(&legacy_state.handle) {
+            Some(legacy) => convert_legacy(&legacy.0),
+            None => return,
+        }
+    } else {
+        return;
+    };
+
+    info!("players: {:?}", header.tanks.iter().map(|f| &f.hash).collect::<Vec<_>>());
+
+    let mut tank_infos = header.tanks;
+    tank_infos.sort_by_key(|f| f.index);
+    let n_tanks = tank_infos.len();
+    let map = Map::from_header(&header.map).expect("invalid map in replay header");
+    playback.tanks = tank_infos
+        .iter()
+        .map(|tank_info| {
+            create_graphics_tank(
+                &mut commands,
+                tank_info,
+                n_tanks,
+                &map,
+                ReplayedTank,
+                results_chassis(results.as_ref(), &tank_info.container_name),
+                &asset_server,
+                &mut meshes,
+                &mut materials,
+            )
+        })
+        .collect();
+    playback.frames = frames;
+    playback.rules = Rules::from_header(&header.rules).expect("invalid rules in replay header");
+    playback.map = map;
+    commands.insert_resource(playback.map.clone());
+    commands.insert_resource(playback.rules.clone());
+    playback.loaded = true;
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Roboto-Regular.ttf"),
+                    font_size: 14.0,
+                    color: Color::BLACK,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_xyz(0.0, Game::HEIGHT / 2. - 15.0, 5.0),
+            ..default()
+        },
+        PlaybackText,
+    ));
+
+    commands.spawn(Camera2dBundle::default());
+}
+
+// space: pause, comma/period: step, up/down: speed, left/right: seek, home/end: start/end
+pub fn replay_playback_controls(keys: Res<Input<KeyCode>>, mut playback: ResMut<ReplayPlayback>) {
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        playback.paused = true;
+        playback.step_forward(1);
+    }
+    if keys.just_pressed(KeyCode::Comma) {
+        playback.paused = true;
+        playback.step_back(1);
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        playback.speed = (playback.speed * 2.0).min(ReplayPlayback::MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        playback.speed = (playback.speed / 2.0).max(ReplayPlayback::MIN_SPEED);
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        playback.step_forward(ReplayPlayback::SEEK_TICKS);
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        playback.step_back(ReplayPlayback::SEEK_TICKS);
+    }
+    if keys.just_pressed(KeyCode::Home) {
+        playback.seek(0);
+    }
+    if keys.just_pressed(KeyCode::End) {
+        let last_tick = playback.last_tick();
+        playback.seek(last_tick);
+    }
+}
+
+pub fn advance_replay_playback(mut playback: ResMut<ReplayPlayback>) {
+    if !playback.loaded || playback.paused {
+        return;
+    }
+
+    playback.progress += playback.speed;
+    while playback.progress >= 1.0 {
+        playback.progress -= 1.0;
+        if playback.tick < playback.last_tick() {
+            playback.tick += 1;
+        }
+    }
+}
+
+pub fn apply_replay_frame(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut query_tank: Query<(&Tank, &mut Transform, &mut Health)>,
+    mut query_transform: Query<&mut Transform, Without<Tank>>,
+    mut query_radar: Query<&mut Radar>,
+    mut query_obstacle: Query<&mut Obstacle>,
+    mut query_bullet: Query<&mut Bullet>,
+) {
+    if !playback.loaded || playback.frames.is_empty() || playback.applied == Some(playback.tick)
+    {
+        return;
+    }
+    let playback = &mut *playback;
+    let frame = &playback.frames[playback.tick];
+
+    for tank_frame in &frame.tanks {
+        let Some(&tank_entity) = playback.tanks.get(tank_frame.index) else {
+            continue;
+        };
+        let (tank, mut transform, mut health) = query_tank.get_mut(tank_entity).unwrap();
+
+        transform.translation.x = tank_frame.x;
+        transform.translation.y = tank_frame.y;
+        transform.rotation = Quat::from_rotation_z(tank_frame.rotation);
+
+        let mut gun_transform = query_transform.get_mut(tank.gun).unwrap();
+        gun_transform.translation.x = tank_frame.x;
+        gun_transform.translation.y = tank_frame.y;
+        gun_transform.rotation = Quat::from_rotation_z(tank_frame.gun_rotation);
+
+        let mut radar_transform = query_transform.get_mut(tank.radar).unwrap();
+        radar_transform.translation.x = tank_frame.x;
+        radar_transform.translation.y = tank_frame.y;
+        radar_transform.rotation = Quat::from_rotation_z(tank_frame.radar_rotation);
+
+        let mut radar = query_radar.get_mut(tank.radar).unwrap();
+        if health.val != tank_frame.health || radar.disabled != tank_frame.radar_disabled {
+            health.val = tank_frame.health;
+            radar.disabled = tank_frame.radar_disabled;
+            commands.entity(tank.radar).insert(RadarNeedsUpdate);
+        }
+    }
+
+    let mut bullets = HashMap::default();
+    for bullet_frame in &frame.bullets {
+        let translation = Vec3::new(bullet_frame.x, bullet_frame.y, 2.5);
+
+        let bullet_entity = match playback.bullets.remove(&bullet_frame.id) {
+            Some(bullet_entity) => {
+                query_transform.get_mut(bullet_entity).unwrap().translation = translation;
+                let mut bullet = query_bullet.get_mut(bullet_entity).unwrap();
+                if bullet.bounces != bullet_frame.bounces {
+                    bullet.bounces = bullet_frame.bounces;
+                }
+                bullet_entity
+            }
+            None => {
+                let Some(&tank) = playback.tanks.get(bullet_frame.tank) else {
+                    continue;
+                };
+                commands
+                    .spawn((
+                        Bullet {
+                            tank,
+                            last_position: translation.truncate(),
+                            distance: 0.,
+                            fired_at: frame.tick,
+                            bounces: bullet_frame.bounces,
+                            // not recorded, only the damage depends on it
+                            power: 1,
+                        },
+                        SpatialBundle {
+                            transform: Transform::from_translation(translation),
+                            visibility: Visibility::Visible,
+                            ..default()
+                        },
+                    ))
+                    .id()
+            }
+        };
+        bullets.insert(bullet_frame.id, bullet_entity);
+    }
+    for (_, bullet_entity) in playback.bullets.drain() {
+        commands.entity(bullet_entity).despawn_recursive();
+    }
+    playback.bullets = bullets;
+
+    // obstacles missing from the frame have been destroyed
+    let mut obstacles = HashMap::default();
+    for obstacle_frame in &frame.obstacles {
+        let Some(def) = playback.map.obstacles.get(obstacle_frame.index) else {
+            continue;
+        };
+        let translation = Vec3::new(obstacle_frame.x, obstacle_frame.y, 1.0);
+
+        let obstacle_entity = match playback.obstacles.remove(&obstacle_frame.index) {
+            Some(obstacle_entity) => {
+                query_transform.get_mut(obstacle_entity).unwrap().translation = translation;
+                query_obstacle.get_mut(obstacle_entity).unwrap().health = obstacle_frame.health;
+                obstacle_entity
+            }
+            None => commands
+                .spawn((
+                    Obstacle {
+                        index: obstacle_frame.index,
+                        width: def.width,
+                        height: def.height,
+                        health: obstacle_frame.health,
+                        path: vec![],
+                        speed: def.speed,
+                        next_waypoint: 0,
+                    },
+                    SpatialBundle {
+                        transform: Transform::from_translation(translation),
+                        visibility: Visibility::Visible,
+                        ..default()
+                    },
+                ))
+                .id(),
+        };
+        obstacles.insert(obstacle_frame.index, obstacle_entity);
+    }
+    for (_, obstacle_entity) in playback.obstacles.drain() {
+        commands.entity(obstacle_entity).despawn_recursive();
+    }
+    playback.obstacles = obstacles;
+
+    playback.applied = Some(playback.tick);
+}
+
+pub fn update_playback_text(
+    playback: Res<ReplayPlayback>,
+    mut query: Query<&mut Text, With<PlaybackText>>,
+) {
+    for mut text in &mut query {
+        text.sections[0].value = format!(
+            "tick {}/{}  x{}{}",
+            playback.tick,
+            playback.last_tick(),
+            playback.speed,
+            if playback.paused { "  paused" } else { "" }
+        );
+    }
+}
+
+pub fn update_replay_safe_zone(playback: Res<ReplayPlayback>, mut zone: ResMut<SafeZone>) {
+    let Some(frame) = playback.frames.get(playback.tick) else {
+        return;
+    };
+
+    let next_zone = SafeZone::at(&playback.rules, frame.tick);
+    if *zone != next_zone {
+        *zone = next_zone;
+    }
+}
@@ -39,6 +39,10 @@ pub fn request_debug_commands(
 
     let command_source = &mut command_sources[debug_toggle.index];
 
+    if command_source.queue.is_empty() {
+        return;
+    }
+
     let mut grouped_commands = command_source.queue[0];
 
     if keys.pressed(KeyCode::W) {
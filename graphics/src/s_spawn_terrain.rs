@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use ctengine::map::{Map, TerrainKind};
+
+#[derive(Component)]
+pub struct TerrainTile;
+
+pub fn terrain_color(kind: TerrainKind) -> Color {
+    match kind {
+        TerrainKind::Mud => Color::rgba(0.4, 0.26, 0.13, 0.6),
+        TerrainKind::Smoke => Color::rgba(0.8, 0.8, 0.8, 0.7),
+    }
+}
+
+// redraws the terrain whenever the map changes, such as when a replay is loaded
+pub fn spawn_terrain(
+    mut commands: Commands,
+    map: Res<Map>,
+    query: Query<Entity, With<TerrainTile>>,
+) {
+    if !map.is_changed() {
+        return;
+    }
+
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for region in &map.terrain {
+        // mud lies on the ground, smoke hangs over the tanks
+        let z = match region.kind {
+            TerrainKind::Mud => 0.5,
+            TerrainKind::Smoke => 6.0,
+        };
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: terrain_color(region.kind),
+                    custom_size: Some(Vec2::new(region.width, region.height)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(region.x, region.y, z),
+                ..default()
+            },
+            TerrainTile,
+        ));
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+use ctengine::{c_tank::Bullet, rules::Rules};
+
+/// Fades bullets with every ricochet, in step with the damage they have left.
+pub fn update_bullet(
+    rules: Res<Rules>,
+    query_bullet: Query<(&Bullet, &Children), Changed<Bullet>>,
+    query_color: Query<&Handle<ColorMaterial>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (bullet, children) in &query_bullet {
+        let alpha = rules
+            .ricochet_damage_factor
+            .powi(bullet.bounces as i32)
+            .max(0.25);
+
+        for &child in children {
+            if let Ok(handle) = query_color.get(child) {
+                let mat = materials.get_mut(handle).unwrap();
+                mat.color.set_a(alpha);
+            }
+        }
+    }
+}
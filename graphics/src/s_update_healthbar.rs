@@ -1,15 +1,18 @@
-use bevy::prelude::{Color, Gizmos, Query, Transform, Vec2, With, ResMut, GizmoConfig};
-use ctengine::{c_health::Health, c_tank::Tank};
+use bevy::prelude::{Color, Gizmos, Query, Transform, Vec2, Visibility, With, ResMut, GizmoConfig};
+use ctengine::{c_chassis::Chassis, c_health::Health, c_tank::Tank};
 
 use crate::c_healthbar::HealthBar;
 
 pub fn update_healthbar(
     mut gizmos: Gizmos,
-    q: Query<(&Transform, &Health), With<Tank>>,
+    q: Query<(&Transform, &Health, &Chassis, &Visibility), With<Tank>>,
     mut config: ResMut<GizmoConfig>,
 ) {
     config.line_width = 5.0;
-    for (p_transform, health) in &q {
+    for (p_transform, health, chassis, visibility) in &q {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
         // let (health, p_transform) = q_parent.get(healthbar.tank).unwrap();
 
         // let polygon = shapes::Rectangle {
@@ -41,16 +44,16 @@ pub fn update_healthbar(
         //     outline_mode: StrokeMode::new(Color::BLACK, 1.0),
         // };
         gizmos.line_2d(
-            Vec2::new(p_transform.translation.x - HealthBar::MAX_WIDTH / 2.0, p_transform.translation.y - Tank::RADIUS - 20.0),
-            Vec2::new(p_transform.translation.x + HealthBar::MAX_WIDTH / 2.0, p_transform.translation.y - Tank::RADIUS - 20.0),
+            Vec2::new(p_transform.translation.x - HealthBar::MAX_WIDTH / 2.0, p_transform.translation.y - chassis.radius() - 20.0),
+            Vec2::new(p_transform.translation.x + HealthBar::MAX_WIDTH / 2.0, p_transform.translation.y - chassis.radius() - 20.0),
             Color::GRAY,
         );
 
         gizmos.line_2d(
-            Vec2::new(p_transform.translation.x - HealthBar::MAX_WIDTH / 2.0, p_transform.translation.y - Tank::RADIUS - 20.0),
-            Vec2::new(p_transform.translation.x - HealthBar::MAX_WIDTH / 2.0 + HealthBar::MAX_WIDTH * (health.val as f32) / (Health::MAX_HEALTH as f32), p_transform.translation.y - Tank::RADIUS - 20.0),
+            Vec2::new(p_transform.translation.x - HealthBar::MAX_WIDTH / 2.0, p_transform.translation.y - chassis.radius() - 20.0),
+            Vec2::new(p_transform.translation.x - HealthBar::MAX_WIDTH / 2.0 + HealthBar::MAX_WIDTH * (health.val as f32) / (chassis.max_health() as f32), p_transform.translation.y - chassis.radius() - 20.0),
            
-            if (health.val as f32) <= (Health::MAX_HEALTH as f32) / 2.0 {
+            if (health.val as f32) <= (chassis.max_health() as f32) / 2.0 {
                 Color::RED
             } else {
                 Color::GREEN
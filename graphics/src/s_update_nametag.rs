@@ -1,16 +1,16 @@
 use bevy::prelude::*;
-use ctengine::c_tank::Tank;
+use ctengine::{c_chassis::Chassis, c_tank::Tank};
 
 use crate::c_nametag::NameTag;
 
 pub fn update_nametag(
-    q_parent: Query<&Transform, With<Tank>>,
+    q_parent: Query<(&Transform, &Chassis), With<Tank>>,
     mut q: Query<(&mut Transform, &NameTag), Without<Tank>>,
 ) {
     for (mut transform, nametag) in &mut q {
-        let p_transform = q_parent.get(nametag.tank).unwrap();
+        let (p_transform, chassis) = q_parent.get(nametag.tank).unwrap();
 
         transform.translation.x = p_transform.translation.x;
-        transform.translation.y = p_transform.translation.y - Tank::RADIUS - 10.0;
+        transform.translation.y = p_transform.translation.y - chassis.radius() - 10.0;
     }
 }
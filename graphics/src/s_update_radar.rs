@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use ctengine::{c_tank::{Tank, Radar}, c_health::Health, c_radar_needs_update::RadarNeedsUpdate};
 
-use crate::s_on_added_bullet::{DISABLED_COLOR, COLORS};
+use crate::s_on_added_bullet::{DISABLED_COLOR, tank_color};
 // use bevy_prototype_lyon::prelude::{DrawMode, FillMode};
 
 // use crate::c_particle::Particle;
@@ -36,7 +36,7 @@ pub fn update_radar(
                     if radar.disabled {
                         mat.color = DISABLED_COLOR.with_a(0.3);
                     } else {
-                        mat.color = COLORS[tank.info.index % COLORS.len()].with_a(0.3);
+                        mat.color = tank_color(tank.info.index).with_a(0.3);
                     }
                 }
 
@@ -0,0 +1,51 @@
+use bevy::{prelude::*, render::render_resource::PrimitiveTopology, sprite::MaterialMesh2dBundle};
+use ctengine::s_safe_zone::SafeZone;
+
+#[derive(Component)]
+pub struct SafeZoneBoundary;
+
+const BOUNDARY_SEGMENTS: usize = 128;
+
+pub fn update_safe_zone_boundary(
+    mut commands: Commands,
+    zone: Res<SafeZone>,
+    mut query: Query<(&mut Transform, &mut Visibility), With<SafeZoneBoundary>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok((mut transform, mut visibility)) = query.get_single_mut() else {
+        if zone.active {
+            // unit circle outline, scaled to the zone radius
+            let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                (0..=BOUNDARY_SEGMENTS)
+                    .map(|i| {
+                        let angle = i as f32 / BOUNDARY_SEGMENTS as f32 * std::f32::consts::TAU;
+                        [angle.cos(), angle.sin(), 0.]
+                    })
+                    .collect::<Vec<[f32; 3]>>(),
+            );
+            commands.spawn((
+                MaterialMesh2dBundle {
+                    mesh: meshes.add(mesh).into(),
+                    material: materials.add(ColorMaterial::from(Color::RED)),
+                    transform: Transform::from_xyz(zone.center.x, zone.center.y, 4.)
+                        .with_scale(Vec3::new(zone.radius, zone.radius, 1.)),
+                    ..default()
+                },
+                SafeZoneBoundary,
+            ));
+        }
+        return;
+    };
+
+    *visibility = if zone.active {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    transform.translation.x = zone.center.x;
+    transform.translation.y = zone.center.y;
+    transform.scale = Vec3::new(zone.radius, zone.radius, 1.);
+}
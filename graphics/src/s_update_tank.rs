@@ -1,21 +1,43 @@
 use bevy::prelude::*;
-use ctengine::{c_health::Health, c_tank::{Tank, Gun}};
+use ctengine::{c_health::Health, c_tank::{Tank, Gun}, c_wreck::WreckMode, rules::Rules};
+
+use crate::tank_tint;
 
 pub fn update_tank(
     // mut commands: Commands,
-    mut query_tank: Query<(&Children, &Tank, &Health), Without<Gun>>,
+    rules: Res<Rules>,
+    mut query_tank: Query<(&Children, &Tank, &Health, &mut Visibility), Without<Gun>>,
     mut query_sprite: Query<&mut Sprite, Without<Gun>>,
-    mut query_gun: Query<&mut Sprite, With<Gun>>,
+    mut query_gun: Query<(&mut Sprite, &mut Visibility), With<Gun>>,
 
     // mut query: Query<(&mut Transform, &mut Tracks), With<Tank>>,
 ) {
-    '_outer: for (children, tank, health) in &mut query_tank {
+    let wreck_color = match rules.wreck {
+        WreckMode::Remain | WreckMode::Removed => Color::BLACK.with_a(0.75),
+        WreckMode::Static => Color::DARK_GRAY,
+        WreckMode::Debris => Color::BLACK.with_a(0.3),
+    };
+
+    '_outer: for (children, tank, health, mut visibility) in &mut query_tank {
+        let (mut gun_sprite, mut gun_visibility) = query_gun.get_mut(tank.gun).unwrap();
+
+        // replays can seek back to before the tank died
+        let shown = if health.val == 0 && rules.wreck == WreckMode::Removed {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+        *visibility = shown;
+        *gun_visibility = shown;
+
         'inner: for &child in children {
             if let Ok(mut tank_sprite) = query_sprite.get_mut(child) {
                 if health.val == 0 {
-                    let gun_sprite: &mut Mut<'_, Sprite> = &mut query_gun.get_mut(tank.gun).unwrap();
-                    tank_sprite.color = Color::BLACK.with_a(0.75);
-                    gun_sprite.color = Color::BLACK.with_a(0.75);
+                    tank_sprite.color = wreck_color;
+                    gun_sprite.color = wreck_color;
+                } else if tank_sprite.color != tank_tint(tank.info.index) {
+                    tank_sprite.color = tank_tint(tank.info.index);
+                    gun_sprite.color = tank_tint(tank.info.index);
                 }
                 break 'inner;
             }
@@ -23,4 +45,4 @@ pub fn update_tank(
 
         
     }
-}
\ No newline at end of file
+}
@@ -30,6 +30,17 @@ pub fn get_db_pool() -> Pool<PostgresConnectionManager<NoTls>> {
             timestamp   TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP(0)
         );
 
+        ALTER TABLE simulations ADD COLUMN IF NOT EXISTS replay BYTEA;
+
+        CREATE TABLE IF NOT EXISTS tank_stats (
+            game_url    VARCHAR NOT NULL,
+            tank_index  INT NOT NULL,
+            tank_hash   VARCHAR NOT NULL,
+            stats       VARCHAR NOT NULL,
+            timestamp   TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP(0),
+            PRIMARY KEY (game_url, tank_index)
+        );
+
         CREATE TABLE IF NOT EXISTS runs (
             container_name  VARCHAR PRIMARY KEY,
             out             VARCHAR NOT NULL,
@@ -111,6 +122,21 @@ pub fn get_simulation_by_url(
         .unwrap()
 }
 
+pub fn get_replay_by_url(
+    client: &mut PooledConnection<PostgresConnectionManager<NoTls>>,
+    game_url: &str,
+) -> Vec<Row> {
+    client
+        .query(
+            "
+                SELECT replay FROM simulations
+                WHERE game_url = $1 AND replay IS NOT NULL
+            ",
+            &[&game_url],
+        )
+        .unwrap()
+}
+
 pub fn upsert_simulation_by_url(
     client: &mut PooledConnection<PostgresConnectionManager<NoTls>>,
     game_url: &str,
@@ -166,3 +192,35 @@ pub fn get_recent_simulations(
         )
         .unwrap()
 }
+
+pub fn get_tank_stats_by_hash(
+    client: &mut PooledConnection<PostgresConnectionManager<NoTls>>,
+    tank_hash: &str,
+) -> Vec<Row> {
+    client
+        .query(
+            "
+                SELECT json_build_object(
+                    'tank_hash', $1::varchar,
+                    'games', COUNT(*),
+                    'shots_fired', SUM((s->>'shots_fired')::int),
+                    'hits', SUM((s->>'hits')::int),
+                    'accuracy', SUM((s->>'hits')::int)::float / NULLIF(SUM((s->>'shots_fired')::int), 0),
+                    'avg_damage_taken', AVG((s->>'damage_taken')::int),
+                    'avg_bullet_damage_taken', AVG((s->>'bullet_damage_taken')::int),
+                    'avg_ram_damage_taken', AVG((s->>'ram_damage_taken')::int),
+                    'avg_wall_damage_taken', AVG((s->>'wall_damage_taken')::int),
+                    'avg_distance_travelled', AVG((s->>'distance_travelled')::float),
+                    'avg_ticks_alive', AVG((s->>'ticks_alive')::int),
+                    'avg_radar_scans', AVG((s->>'radar_scans')::int),
+                    'avg_time_to_first_hit', AVG((s->>'time_to_first_hit')::int)
+                )::varchar
+                FROM (
+                    SELECT stats::json AS s FROM tank_stats
+                    WHERE tank_hash = $1 AND stats != 'null'
+                ) r
+            ",
+            &[&tank_hash],
+        )
+        .unwrap()
+}
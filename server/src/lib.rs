@@ -99,7 +99,9 @@ impl Path {
     pub const RUN: &str = "run";
     pub const SIM: &str = "sim";
     pub const SIM_LOG: &str = "sim_log";
+    pub const REPLAY: &str = "replay";
     pub const RECENT: &str = "recent";
+    pub const TANK_STATS: &str = "tank_stats";
 }
 
 struct Method {}
@@ -112,6 +114,7 @@ struct ContentType {}
 impl ContentType {
     pub const JSON: &str = "application/json";
     pub const TEXT: &str = "text/plain";
+    pub const BINARY: &str = "application/octet-stream";
 }
 
 pub struct StatusLine {}
@@ -148,12 +151,55 @@ impl Response<'_> {
         status_line: StatusLine::NOT_FOUND,
         content: "\"TOO MANY PLAYERS\"",
     };
+
+    pub const ERROR_UNKNOWN_GAME_MODE: Response<'static> = Response {
+        status_line: StatusLine::NOT_FOUND,
+        content: "\"UNKNOWN GAME MODE\"",
+    };
+
+    pub const ERROR_INVALID_RULE_OPTIONS: Response<'static> = Response {
+        status_line: StatusLine::NOT_FOUND,
+        content: "\"INVALID RULE OPTIONS\"",
+    };
 }
 
 const HEADER_PADDING: usize = 150;
 const MAX_BYTES_READ: usize = 1000000;
 const BUFFER_SIZE_BYTES: usize = MAX_BYTES_READ + HEADER_PADDING;
-const MAX_NUMBER_PLAYERS: usize = 4;
+const MAX_NUMBER_PLAYERS: usize = 64;
+// same names as ctengine's GameModeKind, the first one is the default
+const GAME_MODES: &[&str] = &[
+    "deathmatch",
+    "king_of_the_hill",
+    "capture_the_flag",
+    "respawn_deathmatch",
+];
+// rules that can be set with /run/{mode}/{name=value,...}, the simulator parses the values
+// and looks up built-in maps
+const RULE_OPTIONS: &[&str] = &[
+    "teams",
+    "seed",
+    "pickup_interval",
+    "safe_zone",
+    "wrap_around",
+    "wreck",
+    "velocity_collision_damage",
+    "max_collision_damage",
+    "ricochet_bounces",
+    "ricochet_damage_factor",
+    "map",
+];
+
+fn valid_rule_options(options: &str) -> bool {
+    options.split(',').filter(|f| !f.is_empty()).all(|f| match f.split_once('=') {
+        Some((name, value)) => {
+            RULE_OPTIONS.contains(&name)
+                && !value.is_empty()
+                && value.chars().all(|g| g.is_ascii_alphanumeric() || g == '.' || g == '_')
+        }
+        None => false,
+    })
+}
 
 async fn handle_connection(
     mut stream: TcpStream,
@@ -179,6 +225,7 @@ async fn handle_connection(
     let mut string_build = "could not run simulation\n".to_string();
 
     let mut content_type = ContentType::JSON;
+    let mut binary_content: Option<Vec<u8>> = None;
 
     let response: Response = match (method, path) {
         (Method::GET, Path::ROOT) => Response::ROOT_RESPONSE,
@@ -279,8 +326,16 @@ async fn handle_connection(
             let data = get_data_from_request(&request);
             let tank_urls = data.split(' ').collect::<Vec<&str>>();
 
+            // POST /run/{mode}/{options}, /run/{mode}, or just /run for deathmatch
+            let mode = args.first().filter(|f| !f.is_empty()).copied().unwrap_or(GAME_MODES[0]);
+            let options = args.get(1).copied().unwrap_or("");
+
             if tank_urls.len() > MAX_NUMBER_PLAYERS {
                 res = Response::ERROR_TOO_MANY_PLAYERS
+            } else if !GAME_MODES.contains(&mode) {
+                res = Response::ERROR_UNKNOWN_GAME_MODE
+            } else if !valid_rule_options(options) {
+                res = Response::ERROR_INVALID_RULE_OPTIONS
             } else {
                 let invalid_tanks = tank_urls
                     .iter()
@@ -303,13 +358,25 @@ async fn handle_connection(
                         content: &string_build,
                     };
                 } else {
-                    let game_url = &tank_urls.join("-");
+                    // deathmatch keeps the plain url so existing simulations are found
+                    let game_url = &if options.is_empty() && mode == GAME_MODES[0] {
+                        tank_urls.join("-")
+                    } else if options.is_empty() {
+                        format!("{}_{}", tank_urls.join("-"), mode)
+                    } else {
+                        format!("{}_{}_{}", tank_urls.join("-"), mode, options)
+                    };
 
                     println!("run: {}", game_url);
 
                     let mut matches = get_simulation_by_url(db, game_url);
                     if matches.is_empty() {
-                        add_sim_job(&data);
+                        let job_mode = if options.is_empty() {
+                            mode.to_string()
+                        } else {
+                            format!("{}/{}", mode, options)
+                        };
+                        add_sim_job(&format!("{} {}", job_mode, data));
                         upsert_simulation_by_url(db, game_url);
                         matches = get_simulation_by_url(db, game_url);
                     }
@@ -347,6 +414,24 @@ async fn handle_connection(
             }
             res
         }
+        (Method::GET, Path::REPLAY) => {
+            let mut res = Response::NOT_FOUND_RESPONSE;
+
+            println!("get replay: {:?}", args);
+
+            let matches = get_replay_by_url(db, &args.join("-"));
+
+            if !matches.is_empty() {
+                binary_content = Some(matches[0].get(0));
+                content_type = ContentType::BINARY;
+
+                res = Response {
+                    status_line: StatusLine::OK,
+                    content: "",
+                };
+            }
+            res
+        }
         (Method::GET, Path::SIM_LOG) => {
             let mut res = Response::NOT_FOUND_RESPONSE;
 
@@ -371,6 +456,24 @@ async fn handle_connection(
 
             res
         }
+        (Method::GET, Path::TANK_STATS) => {
+            let mut res = Response::NOT_FOUND_RESPONSE;
+
+            println!("get tank_stats: {:?}", args);
+
+            let matches = get_tank_stats_by_hash(db, args[0]);
+
+            if !matches.is_empty() {
+                res_code = matches[0].get(0);
+
+                res = Response {
+                    status_line: StatusLine::OK,
+                    content: &res_code,
+                };
+            }
+
+            res
+        }
         (Method::GET, Path::RECENT) => {
             let mut res = Response::NOT_FOUND_RESPONSE;
 
@@ -392,6 +495,20 @@ async fn handle_connection(
         _ => Response::NOT_FOUND_RESPONSE,
     };
 
+    if let Some(binary_content) = binary_content {
+        let header_string = format!(
+            "{}\r\nContent-Length: {}\r\nContent-Type: {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET\r\n\r\n",
+            response.status_line,
+            binary_content.len(),
+            content_type,
+        );
+
+        stream.write_all(header_string.as_bytes()).await.unwrap();
+        stream.write_all(&binary_content).await.unwrap();
+        stream.flush().await.unwrap();
+        return;
+    }
+
     let response_string = format!(
         "{}\r\nContent-Length: {}\r\nContent-Type: {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET\r\ncharset=UTF-8\r\n\r\n{}",
         response.status_line,
@@ -41,7 +41,8 @@ def root():
 
 @app.get('/view/{game_url}', response_class=HTMLResponse)
 def index(game_url: str):
-    tank_ids = game_url.split("-")
+    # non-deathmatch games end in _{mode}
+    tank_ids = game_url.split("_")[0].split("-")
     # game_url = "".join(tank_ids)
 
     return f"""
@@ -156,4 +157,16 @@ def f4(game_url: str):
     res.headers["Cache-Control"]  = "public, max-age=604800, immutable"
     return res
 
+@app.get('/replay/{game_url}')
+def f5(game_url: str):
+    game_url = game_url[5:-4]
+    r = requests.get(f'http://server:8088/replay/{game_url}')
+
+    if r.status_code != 200:
+        return Response(status_code=404)
+
+    res = Response(content=r.content, media_type="application/octet-stream")
+    res.headers["Cache-Control"]  = "public, max-age=604800, immutable"
+    return res
+
 app.mount("/view/assets", StaticFiles(directory="/ctweb/assets"), name="assets")
\ No newline at end of file
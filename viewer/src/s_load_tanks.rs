@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use ctengine::CustomAssetState;
+use ctengine::{CustomAssetState, ReplayAssetState};
 use wasm_bindgen::prelude::*;
 
 // use crate::CustomAssetState;
@@ -18,13 +18,20 @@ pub fn get_sim_file() -> String {
         .join("-")
 }
 
-pub fn load_tanks_from_file(mut state: ResMut<CustomAssetState>, asset_server: Res<AssetServer>) {
+pub fn load_tanks_from_file(
+    mut state: ResMut<CustomAssetState>,
+    mut replay_state: ResMut<ReplayAssetState>,
+    asset_server: Res<AssetServer>,
+) {
     // state.handle = asset_server.load("./sim.txt");
     let file = format!("/sim/{}.txt", get_sim_file());
+    let replay_file = format!("/replay/{}.ctr", get_sim_file());
 
     println!("sim file: {}", file);
     info!("sim file: {}", file);
 
+    // the legacy log is only used when the match has no recorded replay
+    replay_state.handle = asset_server.load(&replay_file);
     state.handle = asset_server.load(&file);
 
     info!("got file");
@@ -19,6 +19,38 @@ pub fn upload_sim(client: &mut Client, game_url: &str, sim: &str, successful: bo
         .is_ok()
 }
 
+pub fn upload_replay(client: &mut Client, game_url: &str, replay: &[u8]) -> bool {
+    client
+        .execute(
+            r#"
+            UPDATE simulations
+            SET replay = $2
+            WHERE game_url = $1;
+        "#,
+            &[&game_url, &replay],
+        )
+        .is_ok()
+}
+
+pub fn upload_tank_stats(
+    client: &mut Client,
+    game_url: &str,
+    tank_hash: &str,
+    tank_index: i32,
+    stats: &str,
+) -> bool {
+    client
+        .execute(
+            r#"
+            INSERT INTO tank_stats (game_url, tank_index, tank_hash, stats)
+            VALUES($1, $2, $3, $4)
+            ON CONFLICT (game_url, tank_index) DO UPDATE SET stats = EXCLUDED.stats;
+        "#,
+            &[&game_url, &tank_index, &tank_hash, &stats],
+        )
+        .is_ok()
+}
+
 pub fn upload_log_to_db(client: &mut Client, tank_container_name: &str, out: &str, err: &str) -> bool {
     client
         .execute(
@@ -2,6 +2,7 @@ use std::env;
 use std::process::Command as ProcessCommand;
 
 use ct_api::{Command, Commands};
+use ctengine::c_chassis::Chassis;
 use ctengine::c_client::{parse_commands, ClientTrait};
 use ctengine::c_event::CTEvent;
 use db::upload_log_to_db;
@@ -90,6 +91,19 @@ pub struct DockerClient {
 }
 
 impl ClientTrait for DockerClient {
+    fn request_chassis(&mut self) -> Chassis {
+        let output = ProcessCommand::new("bash")
+            .arg("-c")
+            .arg(format!(
+                r#"curl -sS -m 3 {}:8080/info | jq --raw-output '.chassis'"#,
+                self.tank_container_name,
+            ))
+            .output()
+            .expect("failed to communicate with tank");
+
+        Chassis::from_name(&String::from_utf8_lossy(&output.stdout))
+    }
+
     fn request_commands(&mut self) -> Vec<Command> {
         let output = ProcessCommand::new("bash")
             .arg("-c")
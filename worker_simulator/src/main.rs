@@ -2,9 +2,15 @@ use core::time;
 use std::{fs::{self, File}, thread, io::Write};
 
 use ctengine::{remove_tank, c_tank::{TankInfo, AllTankInfo}, s_save_commands::save_commands};
+use ctengine::s_save_replay::{record_replay_events, save_replay, ReplayRecorder};
+use ctengine::s_bullet_physics::bullet_physics;
+use ctengine::s_request_commands_by_event::request_commands_by_event;
+use ctengine::rules::Rules;
+use ctengine::map::Map;
+use ctengine::game_mode::GameModeKind;
 use worker_simulator::{
     create_sim_queue,
-    db::{get_client, upload_sim},
+    db::{get_client, upload_replay, upload_sim, upload_tank_stats},
     get_sim_job, update_sim_job, upload_log, s_setup_sim_tanks::setup_sim_tanks
 };
 use bevy::MinimalPlugins;
@@ -32,14 +38,33 @@ fn main() {
         if !job.is_empty() {
             println!("got {:?}", job);
             let id = &job[0];
-            let tank_hashes = &job[1]
+            let mut tank_hashes = job[1]
                 .split(' ')
                 .map(|f| f.to_string())
                 .collect::<Vec<String>>();
 
+            // jobs start with the game mode and optional rule options as in `{mode}/{options}`,
+            // except ones queued before modes existed
+            let (mode_name, options) = tank_hashes[0]
+                .split_once('/')
+                .map(|(f, g)| (f.to_string(), g.to_string()))
+                .unwrap_or((tank_hashes[0].to_string(), String::new()));
+            let mode = match GameModeKind::from_name(&mode_name) {
+                Some(mode) => {
+                    tank_hashes.remove(0);
+                    mode
+                }
+                None => GameModeKind::Deathmatch,
+            };
+            let tank_hashes = &tank_hashes;
+
             // let tank_container_names = run_docker_game(args);
             // TODO common
-            let game_url: String = tank_hashes.join("-");
+            let game_url: String = match (mode, options.is_empty()) {
+                (GameModeKind::Deathmatch, true) => tank_hashes.join("-"),
+                (_, true) => format!("{}_{}", tank_hashes.join("-"), mode.name()),
+                _ => format!("{}_{}_{}", tank_hashes.join("-"), mode.name(), options),
+            };
             let tank_infos = &tank_hashes.iter().enumerate().map(|(i, f)| TankInfo{
                 hash: f.to_string(),
                 id: format!("{}-{}", f, i),
@@ -60,6 +85,24 @@ fn main() {
             //     .collect::<Vec<String>>();
             thread::sleep(time::Duration::from_millis(5000));
         
+            // the server checked the options when it queued the job
+            let rules = Rules {
+                mode,
+                ..Rules::default()
+            }
+            .with_options(&options)
+            .unwrap_or_else(|err| {
+                println!("ignoring rule options: {}", err);
+                Rules {
+                    mode,
+                    ..Rules::default()
+                }
+            });
+            let map = Map::from_options(&options).unwrap_or_else(|err| {
+                println!("ignoring map: {}", err);
+                Map::default()
+            });
+
             let mut f = File::create("./sim.txt").expect("Unable to create file");
             f.write_all(format!("{}\n", tank_hashes.join(",")).as_bytes())
                 .expect("Unable to write data");
@@ -70,11 +113,22 @@ fn main() {
                     all: tank_infos.to_vec()
                 })
                 .insert_resource(MaxSimulationTicks(600))
+                .insert_resource(rules.clone())
+                .insert_resource(map.clone())
+                .insert_resource(ReplayRecorder::create("./sim.ctr", tank_infos, 600, &rules, &map))
                 .add_systems(Startup, (setup_walls, setup_sim_tanks).chain())
                 .add_plugins(CoreCTPlugin)
                 .add_systems(
                     Update,
-                    save_commands.after(request_commands).before(apply_commands),
+                    (
+                        (save_commands, save_replay)
+                            .chain()
+                            .after(request_commands)
+                            .before(apply_commands),
+                        record_replay_events
+                            .after(bullet_physics)
+                            .before(request_commands_by_event),
+                    ),
                 )
                 .run();
 
@@ -86,6 +140,21 @@ fn main() {
             // println!("hello");
             let sim = fs::read_to_string("./sim.txt").expect("Unable to read file");
             let uploaded_sim = upload_sim(&mut client, &game_url, &sim, true);
+            let replay = fs::read("./sim.ctr").expect("Unable to read file");
+            upload_replay(&mut client, &game_url, &replay);
+
+            // the results json is the last line of the log
+            if let Some(results) = sim.lines().last().and_then(|f| serde_json::from_str::<serde_json::Value>(f).ok()) {
+                for tank_info in tank_infos {
+                    upload_tank_stats(
+                        &mut client,
+                        &game_url,
+                        &tank_info.hash,
+                        tank_info.index as i32,
+                        &results[&tank_info.container_name]["stats"].to_string(),
+                    );
+                }
+            }
             update_sim_job(id, uploaded_sim);
         }
         // println!("no jobs found. sleeping for 1 second");
@@ -1,22 +1,22 @@
 use bevy::prelude::*;
-use ctengine::{c_tank::AllTankInfo, create_gun, create_radar, c_client::Client, create_base_tank};
+use ctengine::{c_tank::AllTankInfo, create_gun, create_radar, c_client::Client, create_base_tank, map::Map, Game};
 
 use crate::DockerClient;
 
-pub fn setup_sim_tanks(state: Res<AllTankInfo>, mut commands: Commands) {
+pub fn setup_sim_tanks(state: Res<AllTankInfo>, map: Res<Map>, mut commands: Commands) {
 
     for tank_info in state.all.iter() {
-        let x = 150.0 * (tank_info.index as f32) + 10.0;
-        let y = 0.0;
+        let Vec2 { x, y } = Game::spawn_position(tank_info.index, state.all.len(), &map);
 
         let gun = create_gun(&mut commands, x, y);
         let radar = create_radar(&mut commands, x, y);
 
-        let client = Client {
+        let mut client = Client {
             client: Box::new(DockerClient {
                 tank_container_name: tank_info.container_name.to_string(),
             }),
         };
-        create_base_tank(tank_info, &mut commands, gun, radar, x, y, client);
+        let chassis = client.client.request_chassis();
+        create_base_tank(tank_info, &mut commands, gun, radar, x, y, client, chassis);
     }
 }